@@ -3,17 +3,23 @@
 //! This module provides lightweight metrics collection with minimal
 //! overhead and capability-based access control.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::thread;
 use std::time::{Duration, Instant};
 
-use metrics::{counter, gauge, histogram, SharedString};
-use metrics::{describe_counter, describe_gauge, describe_histogram, KeyName, Unit};
+use hashbrown::hash_map::RawEntryMut;
+use hashbrown::HashMap as RawHashMap;
+use metrics::{counter, gauge, histogram, Label, SharedString};
+use metrics::{describe_counter, describe_gauge, describe_histogram, KeyName, Unit as MetricsUnit};
 use metrics::{Counter as MetricsCounter, Gauge as MetricsGauge, Histogram as MetricsHistogram};
 use metrics_exporter_prometheus::PrometheusBuilder;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 
 use crate::capability::{ObservabilityCapability, ObservabilityCapabilityChecker};
 use crate::config::MetricsConfig;
@@ -21,6 +27,16 @@ use crate::context::Context;
 use crate::error::ObservabilityError;
 use crate::Result;
 
+/// Which exporter `create_registry` should wire up when metrics are enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsExporter {
+    /// Expose a Prometheus-compatible HTTP scrape endpoint.
+    Prometheus,
+    /// Stream metric update events to connected TCP observers instead of
+    /// exposing a scrape endpoint. See [`TcpMetricsRegistry`].
+    Tcp,
+}
+
 /// Create a metrics registry based on the configuration
 pub fn create_registry(config: &MetricsConfig) -> Result<Box<dyn MetricsRegistry>> {
     if !config.enabled {
@@ -28,13 +44,15 @@ pub fn create_registry(config: &MetricsConfig) -> Result<Box<dyn MetricsRegistry
         return Ok(registry);
     }
 
-    let registry = PrometheusMetricsRegistry::new(config)?;
-    let boxed_registry: Box<dyn MetricsRegistry> = Box::new(registry);
+    let boxed_registry: Box<dyn MetricsRegistry> = match config.exporter {
+        MetricsExporter::Prometheus => Box::new(PrometheusMetricsRegistry::new(config)?),
+        MetricsExporter::Tcp => Box::new(TcpMetricsRegistry::new(config)?),
+    };
     Ok(boxed_registry)
 }
 
 /// Type of metric
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum MetricType {
     /// Counter - monotonically increasing value
     Counter,
@@ -54,6 +72,76 @@ impl fmt::Display for MetricType {
     }
 }
 
+/// The unit a metric's values are measured in, mirroring the taxonomy
+/// exposed by the upstream `metrics` crate's `Unit` type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// A plain count of occurrences
+    Count,
+    /// Count per second
+    CountPerSecond,
+    /// Percentage in `[0, 100]`
+    Percent,
+    /// Bytes
+    Bytes,
+    /// Kibibytes (1024 bytes)
+    Kibibytes,
+    /// Mebibytes (1024 Kibibytes)
+    Mebibytes,
+    /// Gigibytes (1024 Mebibytes)
+    Gigibytes,
+    /// Tebibytes (1024 Gigibytes)
+    Tebibytes,
+    /// Seconds
+    Seconds,
+    /// Milliseconds
+    Milliseconds,
+    /// Microseconds
+    Microseconds,
+    /// Nanoseconds
+    Nanoseconds,
+}
+
+impl Unit {
+    /// The canonical short label for this unit, as used in metric name
+    /// suffixes and exposition output.
+    pub fn canonical_label(&self) -> &'static str {
+        match self {
+            Unit::Count => "count",
+            Unit::CountPerSecond => "count_per_second",
+            Unit::Percent => "percent",
+            Unit::Bytes => "bytes",
+            Unit::Kibibytes => "kibibytes",
+            Unit::Mebibytes => "mebibytes",
+            Unit::Gigibytes => "gigibytes",
+            Unit::Tebibytes => "tebibytes",
+            Unit::Seconds => "seconds",
+            Unit::Milliseconds => "milliseconds",
+            Unit::Microseconds => "microseconds",
+            Unit::Nanoseconds => "nanoseconds",
+        }
+    }
+
+    /// Convert to the upstream `metrics` crate's `Unit`, for use with
+    /// `describe_counter!`/`describe_gauge!`/`describe_histogram!`.
+    fn to_metrics_unit(self) -> MetricsUnit {
+        match self {
+            Unit::Count => MetricsUnit::Count,
+            Unit::CountPerSecond => MetricsUnit::CountPerSecond,
+            Unit::Percent => MetricsUnit::Percent,
+            Unit::Bytes => MetricsUnit::Bytes,
+            Unit::Kibibytes => MetricsUnit::Kibibytes,
+            Unit::Mebibytes => MetricsUnit::Mebibytes,
+            Unit::Gigibytes => MetricsUnit::Gigibytes,
+            Unit::Tebibytes => MetricsUnit::Tebibytes,
+            Unit::Seconds => MetricsUnit::Seconds,
+            Unit::Milliseconds => MetricsUnit::Milliseconds,
+            Unit::Microseconds => MetricsUnit::Microseconds,
+            Unit::Nanoseconds => MetricsUnit::Nanoseconds,
+        }
+    }
+}
+
 /// A metric
 pub trait Metric: Send + Sync {
     /// Get the metric name
@@ -67,6 +155,11 @@ pub trait Metric: Send + Sync {
 
     /// Get the metric labels
     fn labels(&self) -> &HashMap<String, String>;
+
+    /// Get the unit this metric is measured in, if one was configured
+    fn unit(&self) -> Option<Unit> {
+        None
+    }
 }
 
 /// A counter metric (monotonically increasing)
@@ -74,6 +167,16 @@ pub trait Counter: Metric {
     /// Increment the counter by the given amount
     fn increment(&self, value: u64) -> Result<()>;
 
+    /// Increment the counter, attaching request-scoped `extra_labels` to
+    /// this emission on top of the counter's own labels. Lets callers
+    /// record high-cardinality dimensions (status code, route) without
+    /// pre-registering a metric per combination. The default implementation
+    /// ignores `extra_labels` and simply increments.
+    fn increment_with(&self, value: u64, extra_labels: &[(&str, &str)]) -> Result<()> {
+        let _ = extra_labels;
+        self.increment(value)
+    }
+
     /// Get the current value
     fn value(&self) -> u64;
 }
@@ -83,12 +186,33 @@ pub trait Gauge: Metric {
     /// Set the gauge value
     fn set(&self, value: f64) -> Result<()>;
 
+    /// Set the gauge value, attaching request-scoped `extra_labels` to this
+    /// emission. See [`Counter::increment_with`].
+    fn set_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
+        let _ = extra_labels;
+        self.set(value)
+    }
+
     /// Increment the gauge by the given amount
     fn increment(&self, value: f64) -> Result<()>;
 
+    /// Increment the gauge, attaching request-scoped `extra_labels` to this
+    /// emission. See [`Counter::increment_with`].
+    fn increment_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
+        let _ = extra_labels;
+        self.increment(value)
+    }
+
     /// Decrement the gauge by the given amount
     fn decrement(&self, value: f64) -> Result<()>;
 
+    /// Decrement the gauge, attaching request-scoped `extra_labels` to this
+    /// emission. See [`Counter::increment_with`].
+    fn decrement_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
+        let _ = extra_labels;
+        self.decrement(value)
+    }
+
     /// Get the current value
     fn value(&self) -> f64;
 }
@@ -98,8 +222,24 @@ pub trait Histogram: Metric {
     /// Record a value in the histogram
     fn record(&self, value: f64) -> Result<()>;
 
+    /// Record a value, attaching request-scoped `extra_labels` to this
+    /// emission. See [`Counter::increment_with`].
+    fn record_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
+        let _ = extra_labels;
+        self.record(value)
+    }
+
     /// Start timing and return a timer object with an Arc reference
     fn start_timer(&self) -> HistogramTimer;
+
+    /// Get a point-in-time summary of this histogram's recorded
+    /// distribution: count, sum, min, max, mean, and the value at each
+    /// configured quantile. The default implementation returns an empty
+    /// summary; implementations that track a [`QuantileSketch`] should
+    /// override this.
+    fn summary(&self) -> HistogramSummary {
+        HistogramSummary::empty()
+    }
 }
 
 /// A timer for histogram metrics
@@ -147,6 +287,27 @@ impl Drop for HistogramTimer {
     }
 }
 
+/// Time a block or expression (such as a function call or an `.await`ed
+/// future) and record its wall-clock duration (in seconds) into
+/// `$histogram`, yielding the wrapped expression's value. `$body` is
+/// evaluated in place: a closure or `async` block passed as `$body` is not
+/// called or awaited, so only its construction is timed. Removes the boilerplate of manually
+/// capturing [`Instant::now`] and calling `.record()` around every
+/// instrumented call site.
+///
+/// ```ignore
+/// let reply = time!(histogram, llm_client.complete(&prompt).await);
+/// ```
+#[macro_export]
+macro_rules! time {
+    ($histogram:expr, $body:expr) => {{
+        let __time_start = std::time::Instant::now();
+        let __time_result = $body;
+        let _ = $histogram.record(__time_start.elapsed().as_secs_f64());
+        __time_result
+    }};
+}
+
 /// Registry for metrics
 pub trait MetricsRegistry: Send + Sync {
     /// Create or get a counter
@@ -155,6 +316,17 @@ pub trait MetricsRegistry: Send + Sync {
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+    ) -> Result<Arc<dyn Counter>> {
+        self.counter_with_unit(name, description, labels, None)
+    }
+
+    /// Create or get a counter with a configured unit
+    fn counter_with_unit(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
     ) -> Result<Arc<dyn Counter>>;
 
     /// Create or get a gauge
@@ -163,6 +335,17 @@ pub trait MetricsRegistry: Send + Sync {
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+    ) -> Result<Arc<dyn Gauge>> {
+        self.gauge_with_unit(name, description, labels, None)
+    }
+
+    /// Create or get a gauge with a configured unit
+    fn gauge_with_unit(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
     ) -> Result<Arc<dyn Gauge>>;
 
     /// Create or get a histogram
@@ -171,6 +354,17 @@ pub trait MetricsRegistry: Send + Sync {
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+    ) -> Result<Arc<dyn Histogram>> {
+        self.histogram_with_unit(name, description, labels, None)
+    }
+
+    /// Create or get a histogram with a configured unit
+    fn histogram_with_unit(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
     ) -> Result<Arc<dyn Histogram>>;
 
     /// Shutdown the registry
@@ -178,6 +372,238 @@ pub trait MetricsRegistry: Send + Sync {
 
     /// Get the registry name
     fn name(&self) -> &str;
+
+    /// Render this registry's metrics in Prometheus text exposition format
+    /// (`# HELP`/`# TYPE` lines followed by samples). The default
+    /// implementation returns an empty string; only registries that retain
+    /// an enumerable set of metrics (like [`MemoryMetricsRegistry`]) can
+    /// produce meaningful output.
+    fn encode_prometheus(&self) -> String {
+        String::new()
+    }
+
+    /// Render this registry's metrics in OpenMetrics text exposition
+    /// format: identical to [`Self::encode_prometheus`] except counters
+    /// carry a `_total` suffix and the output ends with `# EOF`.
+    fn encode_openmetrics(&self) -> String {
+        String::new()
+    }
+
+    /// Create or get the named histogram and start timing it, returning a
+    /// [`HistogramTimer`] that records the elapsed seconds on `drop` (or
+    /// `stop()`). Creates the histogram lazily if it does not yet exist.
+    fn time_histogram(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+    ) -> Result<HistogramTimer> {
+        let histogram = self.histogram(name, description, labels)?;
+        Ok(histogram.start_timer())
+    }
+
+    /// Create or get a histogram with explicit bucket boundaries.
+    ///
+    /// The default implementation ignores `opts` and forwards to
+    /// [`Self::histogram_with_unit`]; implementations that track per-bucket
+    /// cumulative counters (like [`MemoryMetricsRegistry`]) should override
+    /// this to honor the requested boundaries.
+    ///
+    /// Bucket boundaries are currently honored by [`MemoryMetricsRegistry`]
+    /// only. [`PrometheusMetricsRegistry`] installs its global recorder (and
+    /// the bucket layout backing it) once, at construction time, so a
+    /// per-call `HistogramOpts` has nothing left to configure by the time a
+    /// histogram is created through it; it continues to expose histograms as
+    /// quantile summaries via `self.quantiles`. [`TcpMetricsRegistry`] streams
+    /// raw observations to connected observers rather than aggregating into
+    /// fixed buckets, so bucket boundaries don't apply there either. Callers
+    /// that need real `_bucket`/`_sum`/`_count` series from a scrape endpoint
+    /// should use [`MemoryMetricsRegistry::encode_prometheus`].
+    fn histogram_with_opts(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+        opts: HistogramOpts,
+    ) -> Result<Arc<dyn Histogram>> {
+        let _ = opts;
+        self.histogram_with_unit(name, description, labels, unit)
+    }
+
+    /// Create or get a counter and return it as a type-erased
+    /// [`LabeledMetric`] handle. Callers in a tight instrumentation loop
+    /// should call this once and cache the handle, recording through it
+    /// directly on every subsequent call to bypass the name+label lookup
+    /// entirely.
+    fn counter_handle(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+    ) -> Result<LabeledMetric> {
+        Ok(LabeledMetric::Counter(self.counter(name, description, labels)?))
+    }
+
+    /// Create or get a gauge as a cacheable [`LabeledMetric`] handle. See
+    /// [`Self::counter_handle`].
+    fn gauge_handle(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+    ) -> Result<LabeledMetric> {
+        Ok(LabeledMetric::Gauge(self.gauge(name, description, labels)?))
+    }
+
+    /// Create or get a histogram as a cacheable [`LabeledMetric`] handle.
+    /// See [`Self::counter_handle`].
+    fn histogram_handle(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+    ) -> Result<LabeledMetric> {
+        Ok(LabeledMetric::Histogram(self.histogram(name, description, labels)?))
+    }
+}
+
+/// A type-erased handle to a single registered counter, gauge, or
+/// histogram, returned by [`MetricsRegistry::counter_handle`] and its
+/// gauge/histogram equivalents. Callers that don't need to match on the
+/// metric kind can cache one handle after first registration and record
+/// to it directly, avoiding a repeat name+label lookup on every call.
+#[derive(Clone)]
+pub enum LabeledMetric {
+    /// A counter handle
+    Counter(Arc<dyn Counter>),
+    /// A gauge handle
+    Gauge(Arc<dyn Gauge>),
+    /// A histogram handle
+    Histogram(Arc<dyn Histogram>),
+}
+
+impl LabeledMetric {
+    /// Record `value` against whichever metric kind this handle wraps:
+    /// increments a counter (`value` truncated to `u64`), sets a gauge,
+    /// or records a histogram observation.
+    pub fn observe(&self, value: f64) -> Result<()> {
+        match self {
+            LabeledMetric::Counter(counter) => counter.increment(value as u64),
+            LabeledMetric::Gauge(gauge) => gauge.set(value),
+            LabeledMetric::Histogram(histogram) => histogram.record(value),
+        }
+    }
+}
+
+/// Build the full, deterministically ordered label set for a metric emission,
+/// combining the metric's own stored labels with any call-site `extra_labels`.
+///
+/// If a key appears in both, `extra_labels` wins -- the same last-wins
+/// semantics as merging the two into a single map -- rather than emitting a
+/// duplicate key, which would make the Prometheus/OpenMetrics exposition
+/// invalid.
+///
+/// Labels are constructed as owned [`Label`]s (rather than borrowed `&str`
+/// slices) because the stored label map and any dynamic `extra_labels` are
+/// not `'static`, and `metrics`'s label machinery requires owned data for
+/// anything that isn't a string literal.
+fn build_labels(labels: &HashMap<String, String>, extra_labels: &[(&str, &str)]) -> Vec<Label> {
+    let mut merged = labels.clone();
+    for (key, value) in extra_labels {
+        merged.insert(key.to_string(), value.to_string());
+    }
+    sorted_label_pairs(&merged)
+        .into_iter()
+        .map(|(k, v)| Label::new(k, v))
+        .collect()
+}
+
+/// Sort a label map into a deterministic `(key, value)` order for emission.
+fn sorted_label_pairs(labels: &HashMap<String, String>) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = labels.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    pairs.sort();
+    pairs
+}
+
+/// Validate that each configured histogram quantile lies in `[0, 1]`.
+fn validate_quantiles(quantiles: &[f64]) -> Result<Vec<f64>> {
+    for q in quantiles {
+        if !(0.0..=1.0).contains(q) {
+            return Err(ObservabilityError::MetricsError(format!(
+                "Invalid histogram quantile {}: must be in [0, 1]",
+                q
+            )));
+        }
+    }
+    Ok(quantiles.to_vec())
+}
+
+/// Bucket boundary configuration for a histogram, builder-style.
+///
+/// Defaults to a latency-oriented bucket set (5ms to 10s) suitable for
+/// timing request/response style operations; use [`Self::explicit`],
+/// [`Self::linear`], or [`Self::exponential`] for other distributions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramOpts {
+    buckets: Vec<f64>,
+}
+
+impl HistogramOpts {
+    /// Use an explicit, strictly increasing set of bucket upper bounds.
+    pub fn explicit(buckets: Vec<f64>) -> Result<Self> {
+        validate_buckets(&buckets)?;
+        Ok(Self { buckets })
+    }
+
+    /// `count` buckets starting at `start` and increasing by `width` each
+    /// step.
+    pub fn linear(start: f64, width: f64, count: usize) -> Result<Self> {
+        let buckets: Vec<f64> = (0..count).map(|i| start + width * i as f64).collect();
+        Self::explicit(buckets)
+    }
+
+    /// `count` buckets starting at `start` and multiplying by `factor`
+    /// each step.
+    pub fn exponential(start: f64, factor: f64, count: usize) -> Result<Self> {
+        let mut buckets = Vec::with_capacity(count);
+        let mut value = start;
+        for _ in 0..count {
+            buckets.push(value);
+            value *= factor;
+        }
+        Self::explicit(buckets)
+    }
+
+    /// The configured bucket upper bounds, in increasing order.
+    pub fn buckets(&self) -> &[f64] {
+        &self.buckets
+    }
+}
+
+impl Default for HistogramOpts {
+    fn default() -> Self {
+        Self::explicit(vec![
+            0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+        ])
+        .expect("default histogram buckets are valid")
+    }
+}
+
+/// Validate that `buckets` is strictly increasing and contains no NaN or
+/// infinite bounds.
+fn validate_buckets(buckets: &[f64]) -> Result<()> {
+    if buckets.iter().any(|b| !b.is_finite()) {
+        return Err(ObservabilityError::MetricsError(
+            "Histogram bucket bounds must be finite".to_string(),
+        ));
+    }
+    if !buckets.windows(2).all(|w| w[0] < w[1]) {
+        return Err(ObservabilityError::MetricsError(
+            "Histogram bucket bounds must be strictly increasing".to_string(),
+        ));
+    }
+    Ok(())
 }
 
 /// Metrics registry implementation using Prometheus
@@ -185,15 +611,19 @@ pub struct PrometheusMetricsRegistry {
     name: String,
     initialized: AtomicBool,
     config: MetricsConfig,
+    quantiles: Vec<f64>,
 }
 
 impl PrometheusMetricsRegistry {
     /// Create a new Prometheus metrics registry
     pub fn new(config: &MetricsConfig) -> Result<Self> {
+        let quantiles = validate_quantiles(&config.histogram_quantiles)?;
+
         let registry = Self {
             name: "prometheus_registry".to_string(),
             initialized: AtomicBool::new(false),
             config: config.clone(),
+            quantiles,
         };
 
         // Initialize on construction
@@ -228,6 +658,17 @@ impl PrometheusMetricsRegistry {
                 builder = builder.with_http_listener(endpoint);
             }
 
+            // Render histograms as summaries with our configured quantiles
+            // (e.g. p50/p90/p99/p999) instead of relying on default buckets.
+            if !self.quantiles.is_empty() {
+                builder = builder.set_quantiles(&self.quantiles).map_err(|e| {
+                    ObservabilityError::MetricsError(format!(
+                        "Invalid histogram quantiles: {}",
+                        e
+                    ))
+                })?;
+            }
+
             // Install the prometheus registry
             builder.install().map_err(|e| {
                 ObservabilityError::MetricsError(format!("Failed to install Prometheus: {}", e))
@@ -263,48 +704,71 @@ impl PrometheusMetricsRegistry {
 }
 
 impl MetricsRegistry for PrometheusMetricsRegistry {
-    fn counter(
+    fn counter_with_unit(
         &self,
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+        unit: Option<Unit>,
     ) -> Result<Arc<dyn Counter>> {
         // Add default labels
         let labels = self.add_default_labels(labels);
 
         // Create or get the counter
-        let counter = PrometheusCounter::new(name, description, labels)?;
+        let counter = PrometheusCounter::with_unit(name, description, labels, unit)?;
         Ok(Arc::new(counter))
     }
 
-    fn gauge(
+    fn gauge_with_unit(
         &self,
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+        unit: Option<Unit>,
     ) -> Result<Arc<dyn Gauge>> {
         // Add default labels
         let labels = self.add_default_labels(labels);
 
         // Create or get the gauge
-        let gauge = PrometheusGauge::new(name, description, labels)?;
+        let gauge = PrometheusGauge::with_unit(name, description, labels, unit)?;
         Ok(Arc::new(gauge))
     }
 
-    fn histogram(
+    fn histogram_with_unit(
         &self,
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+        unit: Option<Unit>,
     ) -> Result<Arc<dyn Histogram>> {
         // Add default labels
         let labels = self.add_default_labels(labels);
 
         // Create or get the histogram
-        let histogram = PrometheusHistogram::new(name, description, labels)?;
+        let histogram =
+            PrometheusHistogram::with_quantiles(name, description, labels, unit, self.quantiles.clone())?;
         Ok(Arc::new(histogram))
     }
 
+    // `HistogramOpts` bucket boundaries can't be honored here: the
+    // `PrometheusBuilder` that configures bucket layout for the scrape
+    // endpoint is installed once in `initialize()`, before any individual
+    // histogram (and its opts) exist. `opts` is ignored and the histogram
+    // is created exactly as by `histogram_with_unit`; see the doc comment on
+    // [`MetricsRegistry::histogram_with_opts`] for the full explanation and
+    // where bucket boundaries *are* honored.
+    fn histogram_with_opts(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+        opts: HistogramOpts,
+    ) -> Result<Arc<dyn Histogram>> {
+        let _ = opts;
+        self.histogram_with_unit(name, description, labels, unit)
+    }
+
     fn shutdown(&self) -> Result<()> {
         // No special shutdown needed for Prometheus
         Ok(())
@@ -322,6 +786,7 @@ pub struct PrometheusCounter {
     description: String,
     labels: HashMap<String, String>,
     value: AtomicU64,
+    unit: Option<Unit>,
 }
 
 impl Clone for PrometheusCounter {
@@ -331,6 +796,7 @@ impl Clone for PrometheusCounter {
             description: self.description.clone(),
             labels: self.labels.clone(),
             value: AtomicU64::new(self.value.load(Ordering::Relaxed)),
+            unit: self.unit,
         }
     }
 }
@@ -338,16 +804,34 @@ impl Clone for PrometheusCounter {
 impl PrometheusCounter {
     /// Create a new Prometheus counter
     pub fn new(name: &str, description: &str, labels: HashMap<String, String>) -> Result<Self> {
+        Self::with_unit(name, description, labels, None)
+    }
+
+    /// Create a new Prometheus counter with a configured unit
+    pub fn with_unit(
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+    ) -> Result<Self> {
         // Create the counter
         let counter = Self {
             name: name.to_string(),
             description: description.to_string(),
             labels: labels.clone(),
             value: AtomicU64::new(0),
+            unit,
         };
 
         // Register the counter with metrics
-        describe_counter!(name.to_string(), description.to_string());
+        match unit {
+            Some(unit) => describe_counter!(
+                name.to_string(),
+                unit.to_metrics_unit(),
+                description.to_string()
+            ),
+            None => describe_counter!(name.to_string(), description.to_string()),
+        }
 
         Ok(counter)
     }
@@ -369,20 +853,27 @@ impl Metric for PrometheusCounter {
     fn labels(&self) -> &HashMap<String, String> {
         &self.labels
     }
+
+    fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
 }
 
 impl Counter for PrometheusCounter {
     fn increment(&self, value: u64) -> Result<()> {
+        self.increment_with(value, &[])
+    }
+
+    fn increment_with(&self, value: u64, extra_labels: &[(&str, &str)]) -> Result<()> {
         // Update local value
         self.value.fetch_add(value, Ordering::Relaxed);
 
-        // Update metrics
+        // Update metrics, propagating the counter's own labels (including
+        // any default labels and `plugin_id` added at registration) plus
+        // whatever request-scoped labels the caller attached.
         let name = self.name.clone();
-
-        // Use a simpler approach - directly pass an empty slice to avoid lifetime issues
-        // This is a workaround for the test to pass
-        let empty_labels: &[(&str, &str)] = &[];
-        counter!(name, empty_labels).increment(value);
+        let labels = build_labels(&self.labels, extra_labels);
+        counter!(name, labels).increment(value);
 
         Ok(())
     }
@@ -399,6 +890,7 @@ pub struct PrometheusGauge {
     description: String,
     labels: HashMap<String, String>,
     value: RwLock<f64>,
+    unit: Option<Unit>,
 }
 
 impl Clone for PrometheusGauge {
@@ -408,6 +900,7 @@ impl Clone for PrometheusGauge {
             description: self.description.clone(),
             labels: self.labels.clone(),
             value: RwLock::new(*self.value.read()),
+            unit: self.unit,
         }
     }
 }
@@ -415,16 +908,34 @@ impl Clone for PrometheusGauge {
 impl PrometheusGauge {
     /// Create a new Prometheus gauge
     pub fn new(name: &str, description: &str, labels: HashMap<String, String>) -> Result<Self> {
+        Self::with_unit(name, description, labels, None)
+    }
+
+    /// Create a new Prometheus gauge with a configured unit
+    pub fn with_unit(
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+    ) -> Result<Self> {
         // Create the gauge
         let gauge = Self {
             name: name.to_string(),
             description: description.to_string(),
             labels: labels.clone(),
             value: RwLock::new(0.0),
+            unit,
         };
 
         // Register the gauge with metrics
-        describe_gauge!(name.to_string(), description.to_string());
+        match unit {
+            Some(unit) => describe_gauge!(
+                name.to_string(),
+                unit.to_metrics_unit(),
+                description.to_string()
+            ),
+            None => describe_gauge!(name.to_string(), description.to_string()),
+        }
 
         Ok(gauge)
     }
@@ -446,54 +957,64 @@ impl Metric for PrometheusGauge {
     fn labels(&self) -> &HashMap<String, String> {
         &self.labels
     }
+
+    fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
 }
 
 impl Gauge for PrometheusGauge {
     fn set(&self, value: f64) -> Result<()> {
+        self.set_with(value, &[])
+    }
+
+    fn set_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
         // Update local value
         *self.value.write() = value;
 
-        // Update metrics with proper label format
+        // Update metrics, propagating the gauge's own labels plus any
+        // request-scoped labels the caller attached.
         let name = self.name.clone();
-
-        // Use a simpler approach - directly pass an empty slice to avoid lifetime issues
-        // This is a workaround for the test to pass
-        let empty_labels: &[(&str, &str)] = &[];
-        gauge!(name, empty_labels).set(value);
+        let labels = build_labels(&self.labels, extra_labels);
+        gauge!(name, labels).set(value);
 
         Ok(())
     }
 
     fn increment(&self, value: f64) -> Result<()> {
+        self.increment_with(value, &[])
+    }
+
+    fn increment_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
         // Update local value
         let mut guard = self.value.write();
         *guard += value;
         let new_value = *guard;
+        drop(guard);
 
-        // Update metrics with proper label format
+        // Update metrics with the gauge's own labels plus any request-scoped ones
         let name = self.name.clone();
-
-        // Use a simpler approach - directly pass an empty slice to avoid lifetime issues
-        // This is a workaround for the test to pass
-        let empty_labels: &[(&str, &str)] = &[];
-        gauge!(name, empty_labels).set(new_value);
+        let labels = build_labels(&self.labels, extra_labels);
+        gauge!(name, labels).set(new_value);
 
         Ok(())
     }
 
     fn decrement(&self, value: f64) -> Result<()> {
+        self.decrement_with(value, &[])
+    }
+
+    fn decrement_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
         // Update local value
         let mut guard = self.value.write();
         *guard -= value;
         let new_value = *guard;
+        drop(guard);
 
-        // Update metrics with proper label format
+        // Update metrics with the gauge's own labels plus any request-scoped ones
         let name = self.name.clone();
-
-        // Use a simpler approach - directly pass an empty slice to avoid lifetime issues
-        // This is a workaround for the test to pass
-        let empty_labels: &[(&str, &str)] = &[];
-        gauge!(name, empty_labels).set(new_value);
+        let labels = build_labels(&self.labels, extra_labels);
+        gauge!(name, labels).set(new_value);
 
         Ok(())
     }
@@ -504,25 +1025,76 @@ impl Gauge for PrometheusGauge {
 }
 
 /// Histogram implementation using Prometheus
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct PrometheusHistogram {
     name: String,
     description: String,
     labels: HashMap<String, String>,
+    unit: Option<Unit>,
+    quantiles: Vec<f64>,
+    sketch: Arc<RwLock<QuantileSketch>>,
+}
+
+impl Clone for PrometheusHistogram {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            labels: self.labels.clone(),
+            unit: self.unit,
+            quantiles: self.quantiles.clone(),
+            sketch: Arc::clone(&self.sketch),
+        }
+    }
 }
 
 impl PrometheusHistogram {
     /// Create a new Prometheus histogram
     pub fn new(name: &str, description: &str, labels: HashMap<String, String>) -> Result<Self> {
+        Self::with_unit(name, description, labels, None)
+    }
+
+    /// Create a new Prometheus histogram with a configured unit. Histograms
+    /// measuring durations should use [`Unit::Seconds`] so
+    /// [`HistogramTimer`] self-documents what it records.
+    pub fn with_unit(
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+    ) -> Result<Self> {
+        Self::with_quantiles(name, description, labels, unit, Vec::new())
+    }
+
+    /// Create a new Prometheus histogram that also tracks the given summary
+    /// quantiles (each in `[0, 1]`) via [`Histogram::summary`], backed by
+    /// the same streaming [`QuantileSketch`] used for in-memory snapshots.
+    pub fn with_quantiles(
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+        quantiles: Vec<f64>,
+    ) -> Result<Self> {
         // Create the histogram
         let histogram = Self {
             name: name.to_string(),
             description: description.to_string(),
             labels: labels.clone(),
+            unit,
+            quantiles,
+            sketch: Arc::new(RwLock::new(QuantileSketch::new(0.0039))),
         };
 
         // Register the histogram with metrics
-        describe_histogram!(name.to_string(), description.to_string());
+        match unit {
+            Some(unit) => describe_histogram!(
+                name.to_string(),
+                unit.to_metrics_unit(),
+                description.to_string()
+            ),
+            None => describe_histogram!(name.to_string(), description.to_string()),
+        }
 
         Ok(histogram)
     }
@@ -544,17 +1116,25 @@ impl Metric for PrometheusHistogram {
     fn labels(&self) -> &HashMap<String, String> {
         &self.labels
     }
+
+    fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
 }
 
 impl Histogram for PrometheusHistogram {
     fn record(&self, value: f64) -> Result<()> {
-        // Record the value
-        let name = self.name.clone();
+        self.record_with(value, &[])
+    }
 
-        // Use a simpler approach - directly pass an empty slice to avoid lifetime issues
-        // This is a workaround for the test to pass
-        let empty_labels: &[(&str, &str)] = &[];
-        histogram!(name, empty_labels).record(value);
+    fn record_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
+        self.sketch.write().record(value);
+
+        // Record the value, propagating the histogram's own labels plus any
+        // request-scoped labels the caller attached.
+        let name = self.name.clone();
+        let labels = build_labels(&self.labels, extra_labels);
+        histogram!(name, labels).record(value);
 
         Ok(())
     }
@@ -562,57 +1142,613 @@ impl Histogram for PrometheusHistogram {
     fn start_timer(&self) -> HistogramTimer {
         HistogramTimer::new(Arc::new(self.clone()))
     }
+
+    fn summary(&self) -> HistogramSummary {
+        self.sketch.read().summary(&self.quantiles)
+    }
 }
 
-/// Metrics registry implementation that discards all metrics
-#[derive(Debug, Clone)]
-pub struct NoopMetricsRegistry {
+/// Default capacity of the bounded, drop-oldest ring used to buffer metric
+/// update events while no TCP observer is connected.
+const TCP_EVENT_BUFFER_CAPACITY: usize = 1024;
+
+/// A single metric update, framed and streamed to connected TCP observers.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct TcpMetricEvent {
+    kind: MetricType,
     name: String,
+    labels: Vec<(String, String)>,
+    value: f64,
 }
 
-impl NoopMetricsRegistry {
-    /// Create a new noop metrics registry
-    pub fn new() -> Self {
+/// Encode a metric event as a length-delimited frame: a 4-byte big-endian
+/// length prefix followed by the JSON payload, so a `metrics-observer`-style
+/// client can read events off the wire without ambiguity about where one
+/// ends and the next begins.
+fn encode_frame(event: &TcpMetricEvent) -> Result<Vec<u8>> {
+    let payload = serde_json::to_vec(event).map_err(|e| {
+        ObservabilityError::MetricsError(format!("Failed to encode metric event: {}", e))
+    })?;
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+/// Shared state between the TCP accept loop and the registry's emit path: a
+/// bounded, drop-oldest buffer of events pending delivery plus the
+/// observers currently connected to receive them.
+struct TcpBroadcaster {
+    pending: VecDeque<TcpMetricEvent>,
+    capacity: usize,
+    observers: Vec<TcpStream>,
+}
+
+impl TcpBroadcaster {
+    fn new(capacity: usize) -> Self {
         Self {
-            name: "noop_registry".to_string(),
+            pending: VecDeque::with_capacity(capacity),
+            capacity,
+            observers: Vec::new(),
+        }
+    }
+
+    /// Publish an event. If observers are connected, stream it to each
+    /// directly (dropping any observer whose connection has gone away);
+    /// otherwise buffer it, dropping the oldest buffered event on overflow
+    /// so the hot emit path never blocks on a slow or absent observer.
+    fn publish(&mut self, event: TcpMetricEvent) {
+        if self.observers.is_empty() {
+            if self.pending.len() >= self.capacity {
+                self.pending.pop_front();
+            }
+            self.pending.push_back(event);
+            return;
+        }
+
+        if let Ok(frame) = encode_frame(&event) {
+            self.observers
+                .retain_mut(|stream| stream.write_all(&frame).is_ok());
         }
     }
+
+    /// Attach a newly connected observer, replaying any buffered events to
+    /// it first so it catches up on what it missed while disconnected.
+    fn attach(&mut self, mut stream: TcpStream) {
+        for event in self.pending.drain(..) {
+            let Ok(frame) = encode_frame(&event) else {
+                continue;
+            };
+            if stream.write_all(&frame).is_err() {
+                return;
+            }
+        }
+        self.observers.push(stream);
+    }
 }
 
-impl MetricsRegistry for NoopMetricsRegistry {
-    fn counter(
+/// Metrics registry that streams live metric update events to connected TCP
+/// observers instead of exposing an HTTP scrape endpoint. A
+/// `metrics-observer`-style client can connect to the configured socket and
+/// render live counters, gauge values and histogram samples in real time,
+/// which suits interactive debugging of agent runs where standing up
+/// Prometheus + Grafana is overkill.
+pub struct TcpMetricsRegistry {
+    name: String,
+    config: MetricsConfig,
+    quantiles: Vec<f64>,
+    local_addr: std::net::SocketAddr,
+    broadcaster: Arc<Mutex<TcpBroadcaster>>,
+    shutdown: Arc<AtomicBool>,
+    accept_thread: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+impl TcpMetricsRegistry {
+    /// Create a new TCP streaming registry, binding the accept loop to
+    /// `config.tcp_endpoint` and buffering up to
+    /// `TCP_EVENT_BUFFER_CAPACITY` events while no observer is connected.
+    pub fn new(config: &MetricsConfig) -> Result<Self> {
+        Self::with_capacity(config, TCP_EVENT_BUFFER_CAPACITY)
+    }
+
+    /// Create a new TCP streaming registry with an explicit buffer
+    /// capacity, primarily useful for tests.
+    pub fn with_capacity(config: &MetricsConfig, capacity: usize) -> Result<Self> {
+        let quantiles = validate_quantiles(&config.histogram_quantiles)?;
+
+        let listener = TcpListener::bind(&config.tcp_endpoint).map_err(|e| {
+            ObservabilityError::MetricsError(format!(
+                "Failed to bind TCP metrics listener on {}: {}",
+                config.tcp_endpoint, e
+            ))
+        })?;
+        let local_addr = listener.local_addr().map_err(|e| {
+            ObservabilityError::MetricsError(format!(
+                "Failed to read TCP metrics listener address: {}",
+                e
+            ))
+        })?;
+
+        let broadcaster = Arc::new(Mutex::new(TcpBroadcaster::new(capacity)));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let accept_broadcaster = broadcaster.clone();
+        let accept_shutdown = shutdown.clone();
+        let accept_thread = thread::spawn(move || {
+            for stream in listener.incoming() {
+                // Checked after each blocking accept() returns, including
+                // the one unblocked by shutdown()'s self-connect below.
+                if accept_shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let _ = stream.set_nodelay(true);
+                accept_broadcaster.lock().attach(stream);
+            }
+        });
+
+        Ok(Self {
+            name: "tcp_registry".to_string(),
+            config: config.clone(),
+            quantiles,
+            local_addr,
+            broadcaster,
+            shutdown,
+            accept_thread: Mutex::new(Some(accept_thread)),
+        })
+    }
+
+    /// The socket address the accept loop is actually bound to. Useful when
+    /// `config.tcp_endpoint` binds an ephemeral port (e.g. `"127.0.0.1:0"`),
+    /// such as in tests, where the real port is only known after binding.
+    pub fn local_addr(&self) -> std::net::SocketAddr {
+        self.local_addr
+    }
+
+    /// The address to dial to unblock a blocking `accept()` on
+    /// `self.local_addr`. `TcpListener::bind` on an unspecified address
+    /// (e.g. `0.0.0.0`) still only accepts loopback connections dialed via
+    /// the loopback address, so substitute it in that case; otherwise the
+    /// bound address is directly connectable.
+    fn self_connect_addr(&self) -> std::net::SocketAddr {
+        if self.local_addr.ip().is_unspecified() {
+            let loopback: std::net::IpAddr = if self.local_addr.is_ipv4() {
+                std::net::Ipv4Addr::LOCALHOST.into()
+            } else {
+                std::net::Ipv6Addr::LOCALHOST.into()
+            };
+            std::net::SocketAddr::new(loopback, self.local_addr.port())
+        } else {
+            self.local_addr
+        }
+    }
+
+    /// Add default labels from configuration. Mirrors
+    /// [`PrometheusMetricsRegistry::add_default_labels`].
+    fn add_default_labels(&self, mut labels: HashMap<String, String>) -> HashMap<String, String> {
+        for (key, value) in &self.config.default_labels {
+            if !labels.contains_key(key) {
+                labels.insert(key.clone(), value.clone());
+            }
+        }
+
+        if self.config.include_plugin_id {
+            if let Some(ctx) = Context::current() {
+                if let Some(plugin_id) = ctx.plugin_id {
+                    if !labels.contains_key("plugin_id") {
+                        labels.insert("plugin_id".to_string(), plugin_id);
+                    }
+                }
+            }
+        }
+
+        labels
+    }
+}
+
+impl MetricsRegistry for TcpMetricsRegistry {
+    fn counter_with_unit(
         &self,
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+        unit: Option<Unit>,
     ) -> Result<Arc<dyn Counter>> {
-        Ok(Arc::new(NoopCounter {
+        let labels = self.add_default_labels(labels);
+        Ok(Arc::new(TcpCounter {
             name: name.to_string(),
             description: description.to_string(),
             labels,
+            unit,
+            value: AtomicU64::new(0),
+            broadcaster: self.broadcaster.clone(),
         }))
     }
 
-    fn gauge(
+    fn gauge_with_unit(
         &self,
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+        unit: Option<Unit>,
     ) -> Result<Arc<dyn Gauge>> {
-        Ok(Arc::new(NoopGauge {
+        let labels = self.add_default_labels(labels);
+        Ok(Arc::new(TcpGauge {
             name: name.to_string(),
             description: description.to_string(),
             labels,
+            unit,
+            value: RwLock::new(0.0),
+            broadcaster: self.broadcaster.clone(),
         }))
     }
 
-    fn histogram(
+    fn histogram_with_unit(
         &self,
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+        unit: Option<Unit>,
     ) -> Result<Arc<dyn Histogram>> {
-        Ok(Arc::new(NoopHistogram {
+        let labels = self.add_default_labels(labels);
+        Ok(Arc::new(TcpHistogram {
+            name: name.to_string(),
+            description: description.to_string(),
+            labels,
+            unit,
+            quantiles: self.quantiles.clone(),
+            sketch: Arc::new(Mutex::new(QuantileSketch::new(0.0039))),
+            broadcaster: self.broadcaster.clone(),
+        }))
+    }
+
+    // `HistogramOpts` bucket boundaries don't apply to this registry: it
+    // streams raw observations to connected observers and summarizes via
+    // `self.quantiles`, rather than aggregating into fixed buckets. See the
+    // doc comment on [`MetricsRegistry::histogram_with_opts`].
+    fn histogram_with_opts(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+        opts: HistogramOpts,
+    ) -> Result<Arc<dyn Histogram>> {
+        let _ = opts;
+        self.histogram_with_unit(name, description, labels, unit)
+    }
+
+    fn shutdown(&self) -> Result<()> {
+        // Drop all connected observers, signal the accept loop to stop, and
+        // dial the listener ourselves to unblock its current blocking
+        // accept() call (the connection we create is simply discarded once
+        // the loop observes the shutdown flag and breaks). Then join the
+        // thread so the socket and thread are both released before we
+        // return, rather than leaking both for the life of the process.
+        self.broadcaster.lock().observers.clear();
+        self.shutdown.store(true, Ordering::SeqCst);
+        let _ = TcpStream::connect(self.self_connect_addr());
+        if let Some(handle) = self.accept_thread.lock().take() {
+            let _ = handle.join();
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Drop for TcpMetricsRegistry {
+    /// Ensure the accept thread and listening socket are released even if
+    /// the caller never calls `shutdown()` explicitly.
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+/// Counter implementation that streams updates to connected TCP observers
+pub struct TcpCounter {
+    name: String,
+    description: String,
+    labels: HashMap<String, String>,
+    unit: Option<Unit>,
+    value: AtomicU64,
+    broadcaster: Arc<Mutex<TcpBroadcaster>>,
+}
+
+impl fmt::Debug for TcpCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpCounter")
+            .field("name", &self.name)
+            .field("labels", &self.labels)
+            .field("value", &self.value.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl Metric for TcpCounter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn metric_type(&self) -> MetricType {
+        MetricType::Counter
+    }
+
+    fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
+}
+
+impl Counter for TcpCounter {
+    fn increment(&self, value: u64) -> Result<()> {
+        self.increment_with(value, &[])
+    }
+
+    fn increment_with(&self, value: u64, extra_labels: &[(&str, &str)]) -> Result<()> {
+        let new_value = self.value.fetch_add(value, Ordering::Relaxed) + value;
+
+        let mut labels = sorted_label_pairs(&self.labels);
+        labels.extend(extra_labels.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        self.broadcaster.lock().publish(TcpMetricEvent {
+            kind: MetricType::Counter,
+            name: self.name.clone(),
+            labels,
+            value: new_value as f64,
+        });
+
+        Ok(())
+    }
+
+    fn value(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+/// Gauge implementation that streams updates to connected TCP observers
+pub struct TcpGauge {
+    name: String,
+    description: String,
+    labels: HashMap<String, String>,
+    unit: Option<Unit>,
+    value: RwLock<f64>,
+    broadcaster: Arc<Mutex<TcpBroadcaster>>,
+}
+
+impl fmt::Debug for TcpGauge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpGauge")
+            .field("name", &self.name)
+            .field("labels", &self.labels)
+            .field("value", &*self.value.read())
+            .finish()
+    }
+}
+
+impl TcpGauge {
+    fn publish(&self, value: f64, extra_labels: &[(&str, &str)]) {
+        let mut labels = sorted_label_pairs(&self.labels);
+        labels.extend(extra_labels.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        self.broadcaster.lock().publish(TcpMetricEvent {
+            kind: MetricType::Gauge,
+            name: self.name.clone(),
+            labels,
+            value,
+        });
+    }
+}
+
+impl Metric for TcpGauge {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn metric_type(&self) -> MetricType {
+        MetricType::Gauge
+    }
+
+    fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
+}
+
+impl Gauge for TcpGauge {
+    fn set(&self, value: f64) -> Result<()> {
+        self.set_with(value, &[])
+    }
+
+    fn set_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
+        *self.value.write() = value;
+        self.publish(value, extra_labels);
+        Ok(())
+    }
+
+    fn increment(&self, value: f64) -> Result<()> {
+        self.increment_with(value, &[])
+    }
+
+    fn increment_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
+        let mut guard = self.value.write();
+        *guard += value;
+        let new_value = *guard;
+        drop(guard);
+        self.publish(new_value, extra_labels);
+        Ok(())
+    }
+
+    fn decrement(&self, value: f64) -> Result<()> {
+        self.decrement_with(value, &[])
+    }
+
+    fn decrement_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
+        let mut guard = self.value.write();
+        *guard -= value;
+        let new_value = *guard;
+        drop(guard);
+        self.publish(new_value, extra_labels);
+        Ok(())
+    }
+
+    fn value(&self) -> f64 {
+        *self.value.read()
+    }
+}
+
+/// Histogram implementation that streams samples to connected TCP observers
+pub struct TcpHistogram {
+    name: String,
+    description: String,
+    labels: HashMap<String, String>,
+    unit: Option<Unit>,
+    quantiles: Vec<f64>,
+    sketch: Arc<Mutex<QuantileSketch>>,
+    broadcaster: Arc<Mutex<TcpBroadcaster>>,
+}
+
+impl Clone for TcpHistogram {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            labels: self.labels.clone(),
+            unit: self.unit,
+            quantiles: self.quantiles.clone(),
+            sketch: Arc::clone(&self.sketch),
+            broadcaster: self.broadcaster.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for TcpHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpHistogram")
+            .field("name", &self.name)
+            .field("labels", &self.labels)
+            .finish()
+    }
+}
+
+impl Metric for TcpHistogram {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn metric_type(&self) -> MetricType {
+        MetricType::Histogram
+    }
+
+    fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
+}
+
+impl Histogram for TcpHistogram {
+    fn record(&self, value: f64) -> Result<()> {
+        self.record_with(value, &[])
+    }
+
+    fn record_with(&self, value: f64, extra_labels: &[(&str, &str)]) -> Result<()> {
+        self.sketch.lock().record(value);
+
+        let mut labels = sorted_label_pairs(&self.labels);
+        labels.extend(extra_labels.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        self.broadcaster.lock().publish(TcpMetricEvent {
+            kind: MetricType::Histogram,
+            name: self.name.clone(),
+            labels,
+            value,
+        });
+
+        Ok(())
+    }
+
+    fn summary(&self) -> HistogramSummary {
+        self.sketch.lock().summary(&self.quantiles)
+    }
+
+    fn start_timer(&self) -> HistogramTimer {
+        HistogramTimer::new(Arc::new(self.clone()))
+    }
+}
+
+/// Metrics registry implementation that discards all metrics
+#[derive(Debug, Clone)]
+pub struct NoopMetricsRegistry {
+    name: String,
+}
+
+impl NoopMetricsRegistry {
+    /// Create a new noop metrics registry
+    pub fn new() -> Self {
+        Self {
+            name: "noop_registry".to_string(),
+        }
+    }
+}
+
+impl MetricsRegistry for NoopMetricsRegistry {
+    fn counter_with_unit(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        _unit: Option<Unit>,
+    ) -> Result<Arc<dyn Counter>> {
+        Ok(Arc::new(NoopCounter {
+            name: name.to_string(),
+            description: description.to_string(),
+            labels,
+        }))
+    }
+
+    fn gauge_with_unit(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        _unit: Option<Unit>,
+    ) -> Result<Arc<dyn Gauge>> {
+        Ok(Arc::new(NoopGauge {
+            name: name.to_string(),
+            description: description.to_string(),
+            labels,
+        }))
+    }
+
+    fn histogram_with_unit(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        _unit: Option<Unit>,
+    ) -> Result<Arc<dyn Histogram>> {
+        Ok(Arc::new(NoopHistogram {
             name: name.to_string(),
             description: description.to_string(),
             labels,
@@ -666,19 +1802,1047 @@ impl Counter for NoopCounter {
 
 /// Gauge implementation that discards all metrics
 #[derive(Debug, Clone)]
-struct NoopGauge {
+struct NoopGauge {
+    name: String,
+    description: String,
+    labels: HashMap<String, String>,
+}
+
+impl Metric for NoopGauge {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn metric_type(&self) -> MetricType {
+        MetricType::Gauge
+    }
+
+    fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+}
+
+impl Gauge for NoopGauge {
+    fn set(&self, _value: f64) -> Result<()> {
+        Ok(())
+    }
+
+    fn increment(&self, _value: f64) -> Result<()> {
+        Ok(())
+    }
+
+    fn decrement(&self, _value: f64) -> Result<()> {
+        Ok(())
+    }
+
+    fn value(&self) -> f64 {
+        0.0
+    }
+}
+
+/// Histogram implementation that discards all metrics
+#[derive(Debug, Clone)]
+struct NoopHistogram {
+    name: String,
+    description: String,
+    labels: HashMap<String, String>,
+}
+
+impl Metric for NoopHistogram {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn metric_type(&self) -> MetricType {
+        MetricType::Histogram
+    }
+
+    fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+}
+
+impl Histogram for NoopHistogram {
+    fn record(&self, _value: f64) -> Result<()> {
+        Ok(())
+    }
+
+    fn start_timer(&self) -> HistogramTimer {
+        HistogramTimer::new(Arc::new(self.clone()))
+    }
+}
+
+/// A streaming quantile summary backed by exponentially-sized buckets.
+///
+/// Values are bucketed by `i = ceil(log(v) / log(1 + gamma))`, so reporting
+/// a bucket's representative value `(1 + gamma) ^ i` bounds the relative
+/// error of any quantile query to `gamma`. Memory is bounded by the number
+/// of distinct buckets rather than the number of samples, and summaries
+/// merge cheaply by adding per-bucket counts.
+#[derive(Debug, Clone)]
+pub struct QuantileSketch {
+    gamma: f64,
+    log_gamma: f64,
+    buckets: HashMap<i64, u64>,
+    zero_count: u64,
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl QuantileSketch {
+    /// Create a new sketch with the given relative accuracy (e.g. `0.0039`
+    /// for ~0.4% error).
+    pub fn new(gamma: f64) -> Self {
+        Self {
+            gamma,
+            log_gamma: (1.0 + gamma).ln(),
+            buckets: HashMap::new(),
+            zero_count: 0,
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Record a value into the sketch.
+    pub fn record(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        if value < self.min {
+            self.min = value;
+        }
+        if value > self.max {
+            self.max = value;
+        }
+
+        if value <= 0.0 {
+            self.zero_count += 1;
+            return;
+        }
+
+        let index = (value.ln() / self.log_gamma).ceil() as i64;
+        *self.buckets.entry(index).or_insert(0) += 1;
+    }
+
+    /// Merge another sketch's buckets and summary statistics into this one.
+    pub fn merge(&mut self, other: &QuantileSketch) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.zero_count += other.zero_count;
+        if other.min < self.min {
+            self.min = other.min;
+        }
+        if other.max > self.max {
+            self.max = other.max;
+        }
+        for (bucket, bucket_count) in &other.buckets {
+            *self.buckets.entry(*bucket).or_insert(0) += bucket_count;
+        }
+    }
+
+    /// Total number of recorded values.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Sum of all recorded values.
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Minimum recorded value (0.0 if nothing has been recorded).
+    pub fn min(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.min
+        }
+    }
+
+    /// Maximum recorded value (0.0 if nothing has been recorded).
+    pub fn max(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.max
+        }
+    }
+
+    /// Mean of all recorded values (0.0 if nothing has been recorded).
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+
+    /// Estimate the value at quantile `q` (in `[0, 1]`) by walking buckets in
+    /// order until the cumulative count crosses `q * count`.
+    pub fn quantile(&self, q: f64) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+
+        let target = (q * self.count as f64).ceil().max(1.0) as u64;
+        let mut cumulative = self.zero_count;
+        if cumulative >= target {
+            return 0.0;
+        }
+
+        let mut indices: Vec<&i64> = self.buckets.keys().collect();
+        indices.sort();
+        for index in indices {
+            cumulative += self.buckets[index];
+            if cumulative >= target {
+                return (1.0 + self.gamma).powf(*index as f64);
+            }
+        }
+
+        self.max()
+    }
+
+    /// Build a [`HistogramSummary`] from this sketch for the given list of
+    /// quantiles, each of which should lie in `[0, 1]`.
+    pub fn summary(&self, quantiles: &[f64]) -> HistogramSummary {
+        HistogramSummary {
+            count: self.count(),
+            sum: self.sum(),
+            min: self.min(),
+            max: self.max(),
+            mean: self.mean(),
+            quantiles: quantiles.iter().map(|q| (*q, self.quantile(*q))).collect(),
+        }
+    }
+}
+
+/// Point-in-time summary statistics for a histogram's recorded
+/// distribution, backed by the same streaming [`QuantileSketch`] used for
+/// in-memory snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramSummary {
+    /// Number of recorded values
+    pub count: u64,
+    /// Sum of all recorded values
+    pub sum: f64,
+    /// Minimum recorded value
+    pub min: f64,
+    /// Maximum recorded value
+    pub max: f64,
+    /// Mean of all recorded values
+    pub mean: f64,
+    /// `(quantile, value)` pairs for each configured quantile
+    pub quantiles: Vec<(f64, f64)>,
+}
+
+impl HistogramSummary {
+    /// A summary representing no recorded values.
+    pub fn empty() -> Self {
+        Self {
+            count: 0,
+            sum: 0.0,
+            min: 0.0,
+            max: 0.0,
+            mean: 0.0,
+            quantiles: Vec::new(),
+        }
+    }
+}
+
+/// A serializable point-in-time snapshot of a [`MemoryMetricsRegistry`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MetricsSnapshot {
+    /// Counter values at the time of the snapshot
+    pub counters: Vec<CounterSnapshot>,
+    /// Gauge values at the time of the snapshot
+    pub gauges: Vec<GaugeSnapshot>,
+    /// Histogram summaries at the time of the snapshot
+    pub histograms: Vec<HistogramSnapshot>,
+}
+
+/// A single counter's value in a [`MetricsSnapshot`]
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CounterSnapshot {
+    /// Counter name
+    pub name: String,
+    /// Counter description
+    pub description: String,
+    /// Labels, sorted by key
+    pub labels: Vec<(String, String)>,
+    /// Current value
+    pub value: u64,
+}
+
+/// A single gauge's value in a [`MetricsSnapshot`]
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct GaugeSnapshot {
+    /// Gauge name
+    pub name: String,
+    /// Gauge description
+    pub description: String,
+    /// Labels, sorted by key
+    pub labels: Vec<(String, String)>,
+    /// Current value
+    pub value: f64,
+}
+
+/// A single histogram's distribution summary in a [`MetricsSnapshot`]
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HistogramSnapshot {
+    /// Histogram name
+    pub name: String,
+    /// Histogram description
+    pub description: String,
+    /// Labels, sorted by key
+    pub labels: Vec<(String, String)>,
+    /// Number of recorded values
+    pub count: u64,
+    /// Sum of all recorded values
+    pub sum: f64,
+    /// Minimum recorded value
+    pub min: f64,
+    /// Maximum recorded value
+    pub max: f64,
+    /// 50th percentile
+    pub p50: f64,
+    /// 90th percentile
+    pub p90: f64,
+    /// 99th percentile
+    pub p99: f64,
+    /// 99.9th percentile
+    pub p999: f64,
+    /// Cumulative count of observations at or below each configured bucket
+    /// upper bound, in increasing order. Histograms created without
+    /// explicit [`HistogramOpts`] use [`HistogramOpts::default`]'s bounds;
+    /// empty only if the histogram was configured with an empty bucket list.
+    pub buckets: Vec<(f64, u64)>,
+}
+
+/// Escape a label value for Prometheus/OpenMetrics text exposition: a
+/// backslash, double quote, or newline in the value must be escaped so the
+/// sample line remains parseable.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Render a sorted label list as `{key="value",...}`, or an empty string if
+/// there are no labels.
+fn format_labels(labels: &[(String, String)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let rendered: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape_label_value(v)))
+        .collect();
+    format!("{{{}}}", rendered.join(","))
+}
+
+/// Write the `# HELP`/`# TYPE` block and sample lines for every counter in
+/// `counters`, grouped by name in deterministic (sorted) order.
+fn encode_counters(out: &mut String, counters: &[CounterSnapshot], counter_suffix: &str) {
+    let mut names: Vec<&str> = counters.iter().map(|c| c.name.as_str()).collect();
+    names.sort();
+    names.dedup();
+
+    for name in names {
+        let mut group: Vec<&CounterSnapshot> =
+            counters.iter().filter(|c| c.name == name).collect();
+        group.sort_by(|a, b| a.labels.cmp(&b.labels));
+
+        out.push_str(&format!("# HELP {} {}\n", name, group[0].description));
+        out.push_str(&format!("# TYPE {} counter\n", name));
+        for c in &group {
+            out.push_str(&format!(
+                "{}{}{} {}\n",
+                name,
+                counter_suffix,
+                format_labels(&c.labels),
+                c.value
+            ));
+        }
+    }
+}
+
+/// Write the `# HELP`/`# TYPE` block and sample lines for every gauge in
+/// `gauges`, grouped by name in deterministic (sorted) order.
+fn encode_gauges(out: &mut String, gauges: &[GaugeSnapshot]) {
+    let mut names: Vec<&str> = gauges.iter().map(|g| g.name.as_str()).collect();
+    names.sort();
+    names.dedup();
+
+    for name in names {
+        let mut group: Vec<&GaugeSnapshot> = gauges.iter().filter(|g| g.name == name).collect();
+        group.sort_by(|a, b| a.labels.cmp(&b.labels));
+
+        out.push_str(&format!("# HELP {} {}\n", name, group[0].description));
+        out.push_str(&format!("# TYPE {} gauge\n", name));
+        for g in &group {
+            out.push_str(&format!("{}{} {}\n", name, format_labels(&g.labels), g.value));
+        }
+    }
+}
+
+/// Write the `# HELP`/`# TYPE` block and sample lines for every histogram in
+/// `histograms`, grouped by name in deterministic (sorted) order.
+///
+/// When a histogram has bucket boundaries (see [`HistogramOpts`]; histograms
+/// created without explicit opts get the default set), its real cumulative
+/// bucket counts are emitted. For a histogram with an empty bucket list,
+/// `_bucket{le="..."}` lines are approximated by treating each
+/// tracked quantile's value as a `le` boundary holding that fraction of
+/// the total count, terminated by a `+Inf` bucket with the full count.
+fn encode_histograms(out: &mut String, histograms: &[HistogramSnapshot]) {
+    let mut names: Vec<&str> = histograms.iter().map(|h| h.name.as_str()).collect();
+    names.sort();
+    names.dedup();
+
+    for name in names {
+        let mut group: Vec<&HistogramSnapshot> =
+            histograms.iter().filter(|h| h.name == name).collect();
+        group.sort_by(|a, b| a.labels.cmp(&b.labels));
+
+        out.push_str(&format!("# HELP {} {}\n", name, group[0].description));
+        out.push_str(&format!("# TYPE {} histogram\n", name));
+        for h in &group {
+            if h.buckets.is_empty() {
+                for (quantile, value) in
+                    [(0.5, h.p50), (0.9, h.p90), (0.99, h.p99), (0.999, h.p999)]
+                {
+                    let mut bucket_labels = h.labels.clone();
+                    bucket_labels.push(("le".to_string(), value.to_string()));
+                    let bucket_count = (h.count as f64 * quantile).round() as u64;
+                    out.push_str(&format!(
+                        "{}_bucket{} {}\n",
+                        name,
+                        format_labels(&bucket_labels),
+                        bucket_count
+                    ));
+                }
+            } else {
+                for (bound, count) in &h.buckets {
+                    let mut bucket_labels = h.labels.clone();
+                    bucket_labels.push(("le".to_string(), bound.to_string()));
+                    out.push_str(&format!(
+                        "{}_bucket{} {}\n",
+                        name,
+                        format_labels(&bucket_labels),
+                        count
+                    ));
+                }
+            }
+            let mut inf_labels = h.labels.clone();
+            inf_labels.push(("le".to_string(), "+Inf".to_string()));
+            out.push_str(&format!(
+                "{}_bucket{} {}\n",
+                name,
+                format_labels(&inf_labels),
+                h.count
+            ));
+
+            out.push_str(&format!(
+                "{}_sum{} {}\n",
+                name,
+                format_labels(&h.labels),
+                h.sum
+            ));
+            out.push_str(&format!(
+                "{}_count{} {}\n",
+                name,
+                format_labels(&h.labels),
+                h.count
+            ));
+        }
+    }
+}
+
+/// Render a [`MetricsSnapshot`] as Prometheus (or, with a `_total` counter
+/// suffix, OpenMetrics) text exposition.
+fn encode_exposition(snapshot: &MetricsSnapshot, counter_suffix: &str) -> String {
+    let mut out = String::new();
+    encode_counters(&mut out, &snapshot.counters, counter_suffix);
+    encode_gauges(&mut out, &snapshot.gauges);
+    encode_histograms(&mut out, &snapshot.histograms);
+    out
+}
+
+/// Key used to look up a metric by name and sorted label set
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct MetricKey {
+    name: String,
+    labels: Vec<(String, String)>,
+}
+
+impl MetricKey {
+    fn new(name: &str, labels: &HashMap<String, String>) -> Self {
+        let mut labels: Vec<(String, String)> =
+            labels.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        labels.sort();
+        Self {
+            name: name.to_string(),
+            labels,
+        }
+    }
+}
+
+/// Hash `key` once under `hash_builder`, the same [`BuildHasher`] backing a
+/// [`RawHashMap`]. The result can be reused across a read-lock raw-entry
+/// lookup and, on a miss, a write-lock raw-entry insert-or-find, so a
+/// `(name, sorted-labels)` key is hashed exactly once per
+/// `*_with_unit`/`histogram_with_opts` call regardless of which branch is
+/// taken.
+fn hash_key<K: Hash + ?Sized, S: BuildHasher>(hash_builder: &S, key: &K) -> u64 {
+    hash_builder.hash_one(key)
+}
+
+/// Tracks when a metric was last updated and how many times, so an idle
+/// sweep can tell a genuinely-stale metric from one that simply wasn't
+/// re-observed between two sweeps.
+#[derive(Debug)]
+struct Recency {
+    generation: AtomicU64,
+    swept_generation: AtomicU64,
+    last_seen: RwLock<Instant>,
+}
+
+impl Recency {
+    fn new() -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+            swept_generation: AtomicU64::new(u64::MAX),
+            last_seen: RwLock::new(Instant::now()),
+        }
+    }
+
+    /// Bump the generation and last-seen time; called on every write.
+    ///
+    /// `last_seen` is written *before* `generation` is bumped, not after:
+    /// that ordering guarantees any reader that observes a new `generation`
+    /// also observes the fresh `last_seen` it goes with, so the two fields
+    /// can be read independently (without a shared lock) and never be seen
+    /// in a half-updated state. Writing them in the opposite order would let
+    /// a reader see a bumped generation paired with a stale `last_seen`,
+    /// which is exactly the race that could cause a concurrently-touched
+    /// metric to be evicted.
+    fn touch(&self) {
+        *self.last_seen.write() = Instant::now();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Check whether this metric is idle under `policy`, recording the
+    /// generation observed by *this* sweep for the next one to compare
+    /// against. The generation is sampled before the idle check so a value
+    /// updated concurrently during the sweep is never evicted: because
+    /// `touch()` writes `last_seen` before bumping `generation` (see its
+    /// doc comment), any `touch()` racing with this read either hasn't
+    /// bumped `generation` yet (in which case `last_seen` may already be
+    /// fresh, which keeps the metric out of the timeout window) or has
+    /// bumped it (in which case `last_seen` is guaranteed fresh too). Either
+    /// way a racing touch cannot be read as both idle-looking `last_seen`
+    /// and matching `generation` at once.
+    fn is_idle(&self, now: Instant, timeout: Duration) -> bool {
+        let generation = self.generation.load(Ordering::SeqCst);
+        let last_seen = *self.last_seen.read();
+        let previous = self.swept_generation.swap(generation, Ordering::SeqCst);
+        previous == generation && now.duration_since(last_seen) >= timeout
+    }
+}
+
+#[derive(Debug)]
+struct MemoryCounterState {
+    description: String,
+    labels: HashMap<String, String>,
+    value: AtomicU64,
+    unit: Option<Unit>,
+    recency: Recency,
+}
+
+#[derive(Debug)]
+struct MemoryGaugeState {
+    description: String,
+    labels: HashMap<String, String>,
+    value: RwLock<f64>,
+    unit: Option<Unit>,
+    recency: Recency,
+}
+
+#[derive(Debug)]
+struct MemoryHistogramState {
+    description: String,
+    labels: HashMap<String, String>,
+    sketch: parking_lot::Mutex<QuantileSketch>,
+    unit: Option<Unit>,
+    recency: Recency,
+    /// Bucket upper bounds this histogram was configured with, in
+    /// increasing order; [`HistogramOpts::default`]'s bounds if created
+    /// without explicit opts, empty only for an empty bucket list.
+    buckets: Vec<f64>,
+    /// Cumulative count of observations at or below `buckets[i]`, indexed
+    /// the same as `buckets`.
+    bucket_counts: Vec<AtomicU64>,
+    /// Quantiles reported by [`MemoryHistogram::summary`]. Matches the
+    /// fixed p50/p90/p99/p999 set that [`MemoryMetricsRegistry::snapshot`]
+    /// computes for every histogram, so the two agree on the same state.
+    quantiles: Vec<f64>,
+}
+
+/// Quantiles [`MemoryHistogramState`] reports, matching the p50/p90/p99/p999
+/// set [`MemoryMetricsRegistry::snapshot`] computes for every histogram.
+const MEMORY_HISTOGRAM_SUMMARY_QUANTILES: [f64; 4] = [0.5, 0.9, 0.99, 0.999];
+
+/// Selects which kinds of metrics are eligible for idle eviction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetricKindMask(u8);
+
+impl MetricKindMask {
+    /// No metric kinds are eligible
+    pub const NONE: Self = Self(0);
+    /// Counters are eligible
+    pub const COUNTER: Self = Self(1 << 0);
+    /// Gauges are eligible
+    pub const GAUGE: Self = Self(1 << 1);
+    /// Histograms are eligible
+    pub const HISTOGRAM: Self = Self(1 << 2);
+    /// All metric kinds are eligible
+    pub const ALL: Self = Self(Self::COUNTER.0 | Self::GAUGE.0 | Self::HISTOGRAM.0);
+
+    /// Whether `kind` is selected by this mask
+    pub fn contains(self, kind: MetricType) -> bool {
+        let bit = match kind {
+            MetricType::Counter => Self::COUNTER.0,
+            MetricType::Gauge => Self::GAUGE.0,
+            MetricType::Histogram => Self::HISTOGRAM.0,
+        };
+        self.0 & bit != 0
+    }
+}
+
+impl std::ops::BitOr for MetricKindMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Configuration for idle-metric eviction: metrics of a selected kind that
+/// go unchanged for longer than `idle_timeout` are dropped from the
+/// registry so long-running processes with high-cardinality, transient
+/// label sets don't grow without bound.
+#[derive(Debug, Clone, Copy)]
+pub struct EvictionPolicy {
+    /// How long a metric may go unchanged before it becomes eligible for eviction
+    pub idle_timeout: Duration,
+    /// Which kinds of metrics are eligible for eviction
+    pub kinds: MetricKindMask,
+}
+
+impl EvictionPolicy {
+    /// Create a new eviction policy
+    pub fn new(idle_timeout: Duration, kinds: MetricKindMask) -> Self {
+        Self {
+            idle_timeout,
+            kinds,
+        }
+    }
+
+    /// An eviction policy that never evicts anything
+    pub fn disabled() -> Self {
+        Self {
+            idle_timeout: Duration::MAX,
+            kinds: MetricKindMask::NONE,
+        }
+    }
+}
+
+impl Default for EvictionPolicy {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+/// Metrics registry implementation that retains all recorded values in
+/// process and can produce a serializable [`MetricsSnapshot`] on demand.
+///
+/// Unlike [`PrometheusMetricsRegistry`], which hands values off to the
+/// global `metrics` recorder, this registry is the source of truth for its
+/// own state, which makes it useful for offline inspection and testing
+/// without standing up a scrape endpoint.
+pub struct MemoryMetricsRegistry {
+    name: String,
+    gamma: f64,
+    eviction: EvictionPolicy,
+    counters: RwLock<RawHashMap<MetricKey, Arc<MemoryCounterState>>>,
+    gauges: RwLock<RawHashMap<MetricKey, Arc<MemoryGaugeState>>>,
+    histograms: RwLock<RawHashMap<MetricKey, Arc<MemoryHistogramState>>>,
+}
+
+impl MemoryMetricsRegistry {
+    /// Create a new in-memory metrics registry with the default quantile
+    /// sketch accuracy (~0.4% relative error) and no idle eviction.
+    pub fn new() -> Self {
+        Self::with_accuracy(0.0039)
+    }
+
+    /// Create a new in-memory metrics registry whose histograms use the
+    /// given quantile sketch accuracy (relative error as a fraction, e.g.
+    /// `0.0039` for ~0.4%).
+    pub fn with_accuracy(gamma: f64) -> Self {
+        Self {
+            name: "memory_registry".to_string(),
+            gamma,
+            eviction: EvictionPolicy::disabled(),
+            counters: RwLock::new(RawHashMap::new()),
+            gauges: RwLock::new(RawHashMap::new()),
+            histograms: RwLock::new(RawHashMap::new()),
+        }
+    }
+
+    /// Create a new in-memory metrics registry with idle-metric eviction
+    /// enabled under the given policy.
+    pub fn with_eviction(gamma: f64, eviction: EvictionPolicy) -> Self {
+        Self {
+            eviction,
+            ..Self::with_accuracy(gamma)
+        }
+    }
+
+    /// Evict metrics that have been idle longer than the configured policy
+    /// allows. Called lazily from [`Self::snapshot`]; callers with a
+    /// background task can also invoke it directly on a timer.
+    pub fn sweep_idle(&self) {
+        let now = Instant::now();
+        let timeout = self.eviction.idle_timeout;
+
+        if self.eviction.kinds.contains(MetricType::Counter) {
+            self.counters
+                .write()
+                .retain(|_, state| !state.recency.is_idle(now, timeout));
+        }
+        if self.eviction.kinds.contains(MetricType::Gauge) {
+            self.gauges
+                .write()
+                .retain(|_, state| !state.recency.is_idle(now, timeout));
+        }
+        if self.eviction.kinds.contains(MetricType::Histogram) {
+            self.histograms
+                .write()
+                .retain(|_, state| !state.recency.is_idle(now, timeout));
+        }
+    }
+
+    /// Produce a serializable snapshot of every metric currently tracked,
+    /// after first sweeping out anything idle.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.sweep_idle();
+
+        let counters = self
+            .counters
+            .read()
+            .iter()
+            .map(|(key, state)| CounterSnapshot {
+                name: key.name.clone(),
+                description: state.description.clone(),
+                labels: key.labels.clone(),
+                value: state.value.load(Ordering::Relaxed),
+            })
+            .collect();
+
+        let gauges = self
+            .gauges
+            .read()
+            .iter()
+            .map(|(key, state)| GaugeSnapshot {
+                name: key.name.clone(),
+                description: state.description.clone(),
+                labels: key.labels.clone(),
+                value: *state.value.read(),
+            })
+            .collect();
+
+        let histograms = self
+            .histograms
+            .read()
+            .iter()
+            .map(|(key, state)| {
+                let sketch = state.sketch.lock();
+                HistogramSnapshot {
+                    name: key.name.clone(),
+                    description: state.description.clone(),
+                    labels: key.labels.clone(),
+                    count: sketch.count(),
+                    sum: sketch.sum(),
+                    min: sketch.min(),
+                    max: sketch.max(),
+                    p50: sketch.quantile(0.5),
+                    p90: sketch.quantile(0.9),
+                    p99: sketch.quantile(0.99),
+                    p999: sketch.quantile(0.999),
+                    buckets: state
+                        .buckets
+                        .iter()
+                        .zip(state.bucket_counts.iter())
+                        .map(|(bound, count)| (*bound, count.load(Ordering::Relaxed)))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        MetricsSnapshot {
+            counters,
+            gauges,
+            histograms,
+        }
+    }
+}
+
+impl Default for MemoryMetricsRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricsRegistry for MemoryMetricsRegistry {
+    fn counter_with_unit(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+    ) -> Result<Arc<dyn Counter>> {
+        let key = MetricKey::new(name, &labels);
+
+        // Fast path: hash `key` once and reuse that hash for both a
+        // read-lock raw-entry lookup (the overwhelmingly common case of an
+        // already-registered metric in a hot instrumentation loop, with no
+        // write-lock contention) and, on a miss, the write-lock raw-entry
+        // insert-or-find below -- `key` is hashed exactly once per call
+        // either way. Callers that want to skip the lookup entirely on
+        // every call, warm or cold, should cache the `LabeledMetric` handle
+        // returned by `counter_handle` instead.
+        let hash = {
+            let counters = self.counters.read();
+            let hash = hash_key(counters.hasher(), &key);
+            if let Some((_, state)) = counters.raw_entry().from_key_hashed_nocheck(hash, &key) {
+                return Ok(Arc::new(MemoryCounter {
+                    name: name.to_string(),
+                    state: state.clone(),
+                }));
+            }
+            hash
+        };
+
+        let mut counters = self.counters.write();
+        let state = match counters.raw_entry_mut().from_key_hashed_nocheck(hash, &key) {
+            RawEntryMut::Occupied(entry) => entry.into_mut().clone(),
+            RawEntryMut::Vacant(entry) => {
+                let state = Arc::new(MemoryCounterState {
+                    description: description.to_string(),
+                    labels,
+                    value: AtomicU64::new(0),
+                    unit,
+                    recency: Recency::new(),
+                });
+                entry.insert_hashed_nocheck(hash, key, state).1.clone()
+            }
+        };
+
+        Ok(Arc::new(MemoryCounter {
+            name: name.to_string(),
+            state,
+        }))
+    }
+
+    fn gauge_with_unit(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+    ) -> Result<Arc<dyn Gauge>> {
+        let key = MetricKey::new(name, &labels);
+
+        // Fast path: see the comment in `counter_with_unit`.
+        let hash = {
+            let gauges = self.gauges.read();
+            let hash = hash_key(gauges.hasher(), &key);
+            if let Some((_, state)) = gauges.raw_entry().from_key_hashed_nocheck(hash, &key) {
+                return Ok(Arc::new(MemoryGauge {
+                    name: name.to_string(),
+                    state: state.clone(),
+                }));
+            }
+            hash
+        };
+
+        let mut gauges = self.gauges.write();
+        let state = match gauges.raw_entry_mut().from_key_hashed_nocheck(hash, &key) {
+            RawEntryMut::Occupied(entry) => entry.into_mut().clone(),
+            RawEntryMut::Vacant(entry) => {
+                let state = Arc::new(MemoryGaugeState {
+                    description: description.to_string(),
+                    labels,
+                    value: RwLock::new(0.0),
+                    unit,
+                    recency: Recency::new(),
+                });
+                entry.insert_hashed_nocheck(hash, key, state).1.clone()
+            }
+        };
+
+        Ok(Arc::new(MemoryGauge {
+            name: name.to_string(),
+            state,
+        }))
+    }
+
+    fn histogram_with_unit(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+    ) -> Result<Arc<dyn Histogram>> {
+        self.histogram_with_opts(name, description, labels, unit, HistogramOpts::default())
+    }
+
+    fn histogram_with_opts(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+        opts: HistogramOpts,
+    ) -> Result<Arc<dyn Histogram>> {
+        let key = MetricKey::new(name, &labels);
+
+        // Fast path: see the comment in `counter_with_unit`.
+        let hash = {
+            let histograms = self.histograms.read();
+            let hash = hash_key(histograms.hasher(), &key);
+            if let Some((_, state)) = histograms.raw_entry().from_key_hashed_nocheck(hash, &key) {
+                return Ok(Arc::new(MemoryHistogram {
+                    name: name.to_string(),
+                    state: state.clone(),
+                }));
+            }
+            hash
+        };
+
+        let gamma = self.gamma;
+        let mut histograms = self.histograms.write();
+        let state = match histograms.raw_entry_mut().from_key_hashed_nocheck(hash, &key) {
+            RawEntryMut::Occupied(entry) => entry.into_mut().clone(),
+            RawEntryMut::Vacant(entry) => {
+                let bucket_counts = opts.buckets.iter().map(|_| AtomicU64::new(0)).collect();
+                let state = Arc::new(MemoryHistogramState {
+                    description: description.to_string(),
+                    labels,
+                    sketch: parking_lot::Mutex::new(QuantileSketch::new(gamma)),
+                    unit,
+                    recency: Recency::new(),
+                    buckets: opts.buckets,
+                    bucket_counts,
+                    quantiles: MEMORY_HISTOGRAM_SUMMARY_QUANTILES.to_vec(),
+                });
+                entry.insert_hashed_nocheck(hash, key, state).1.clone()
+            }
+        };
+
+        Ok(Arc::new(MemoryHistogram {
+            name: name.to_string(),
+            state,
+        }))
+    }
+
+    fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn encode_prometheus(&self) -> String {
+        encode_exposition(&self.snapshot(), "")
+    }
+
+    fn encode_openmetrics(&self) -> String {
+        let mut out = encode_exposition(&self.snapshot(), "_total");
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+/// Counter implementation backed by [`MemoryMetricsRegistry`]
+#[derive(Debug, Clone)]
+pub struct MemoryCounter {
+    name: String,
+    state: Arc<MemoryCounterState>,
+}
+
+impl Metric for MemoryCounter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.state.description
+    }
+
+    fn metric_type(&self) -> MetricType {
+        MetricType::Counter
+    }
+
+    fn labels(&self) -> &HashMap<String, String> {
+        &self.state.labels
+    }
+
+    fn unit(&self) -> Option<Unit> {
+        self.state.unit
+    }
+}
+
+impl Counter for MemoryCounter {
+    fn increment(&self, value: u64) -> Result<()> {
+        self.state.value.fetch_add(value, Ordering::Relaxed);
+        self.state.recency.touch();
+        Ok(())
+    }
+
+    fn value(&self) -> u64 {
+        self.state.value.load(Ordering::Relaxed)
+    }
+}
+
+/// Gauge implementation backed by [`MemoryMetricsRegistry`]
+#[derive(Debug, Clone)]
+pub struct MemoryGauge {
     name: String,
-    description: String,
-    labels: HashMap<String, String>,
+    state: Arc<MemoryGaugeState>,
 }
 
-impl Metric for NoopGauge {
+impl Metric for MemoryGauge {
     fn name(&self) -> &str {
         &self.name
     }
 
     fn description(&self) -> &str {
-        &self.description
+        &self.state.description
     }
 
     fn metric_type(&self) -> MetricType {
@@ -686,43 +2850,52 @@ impl Metric for NoopGauge {
     }
 
     fn labels(&self) -> &HashMap<String, String> {
-        &self.labels
+        &self.state.labels
+    }
+
+    fn unit(&self) -> Option<Unit> {
+        self.state.unit
     }
 }
 
-impl Gauge for NoopGauge {
-    fn set(&self, _value: f64) -> Result<()> {
+impl Gauge for MemoryGauge {
+    fn set(&self, value: f64) -> Result<()> {
+        *self.state.value.write() = value;
+        self.state.recency.touch();
         Ok(())
     }
 
-    fn increment(&self, _value: f64) -> Result<()> {
+    fn increment(&self, value: f64) -> Result<()> {
+        *self.state.value.write() += value;
+        self.state.recency.touch();
         Ok(())
     }
 
-    fn decrement(&self, _value: f64) -> Result<()> {
+    fn decrement(&self, value: f64) -> Result<()> {
+        *self.state.value.write() -= value;
+        self.state.recency.touch();
         Ok(())
     }
 
     fn value(&self) -> f64 {
-        0.0
+        *self.state.value.read()
     }
 }
 
-/// Histogram implementation that discards all metrics
+/// Histogram implementation backed by [`MemoryMetricsRegistry`]
 #[derive(Debug, Clone)]
-struct NoopHistogram {
+pub struct MemoryHistogram {
     name: String,
-    description: String,
-    labels: HashMap<String, String>,
+    state: Arc<MemoryHistogramState>,
 }
 
-impl Metric for NoopHistogram {
+impl Metric for MemoryHistogram {
     fn name(&self) -> &str {
         &self.name
     }
 
     fn description(&self) -> &str {
-        &self.description
+        &self.state.description
     }
 
     fn metric_type(&self) -> MetricType {
@@ -730,18 +2903,33 @@ impl Metric for NoopHistogram {
     }
 
     fn labels(&self) -> &HashMap<String, String> {
-        &self.labels
+        &self.state.labels
+    }
+
+    fn unit(&self) -> Option<Unit> {
+        self.state.unit
     }
 }
 
-impl Histogram for NoopHistogram {
-    fn record(&self, _value: f64) -> Result<()> {
+impl Histogram for MemoryHistogram {
+    fn record(&self, value: f64) -> Result<()> {
+        self.state.sketch.lock().record(value);
+        for (bound, count) in self.state.buckets.iter().zip(self.state.bucket_counts.iter()) {
+            if value <= *bound {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.state.recency.touch();
         Ok(())
     }
 
     fn start_timer(&self) -> HistogramTimer {
         HistogramTimer::new(Arc::new(self.clone()))
     }
+
+    fn summary(&self) -> HistogramSummary {
+        self.state.sketch.lock().summary(&self.state.quantiles)
+    }
 }
 
 /// Metrics registry implementation that enforces capability checks
@@ -776,11 +2964,12 @@ impl CapabilityMetricsRegistry {
 }
 
 impl MetricsRegistry for CapabilityMetricsRegistry {
-    fn counter(
+    fn counter_with_unit(
         &self,
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+        unit: Option<Unit>,
     ) -> Result<Arc<dyn Counter>> {
         // Check capability
         if !self.check_capability()? {
@@ -789,14 +2978,15 @@ impl MetricsRegistry for CapabilityMetricsRegistry {
             ));
         }
 
-        self.inner.counter(name, description, labels)
+        self.inner.counter_with_unit(name, description, labels, unit)
     }
 
-    fn gauge(
+    fn gauge_with_unit(
         &self,
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+        unit: Option<Unit>,
     ) -> Result<Arc<dyn Gauge>> {
         // Check capability
         if !self.check_capability()? {
@@ -805,14 +2995,34 @@ impl MetricsRegistry for CapabilityMetricsRegistry {
             ));
         }
 
-        self.inner.gauge(name, description, labels)
+        self.inner.gauge_with_unit(name, description, labels, unit)
     }
 
-    fn histogram(
+    fn histogram_with_unit(
+        &self,
+        name: &str,
+        description: &str,
+        labels: HashMap<String, String>,
+        unit: Option<Unit>,
+    ) -> Result<Arc<dyn Histogram>> {
+        // Check capability
+        if !self.check_capability()? {
+            return Err(ObservabilityError::CapabilityError(
+                "Missing metrics capability".to_string(),
+            ));
+        }
+
+        self.inner
+            .histogram_with_unit(name, description, labels, unit)
+    }
+
+    fn histogram_with_opts(
         &self,
         name: &str,
         description: &str,
         labels: HashMap<String, String>,
+        unit: Option<Unit>,
+        opts: HistogramOpts,
     ) -> Result<Arc<dyn Histogram>> {
         // Check capability
         if !self.check_capability()? {
@@ -821,7 +3031,16 @@ impl MetricsRegistry for CapabilityMetricsRegistry {
             ));
         }
 
-        self.inner.histogram(name, description, labels)
+        self.inner
+            .histogram_with_opts(name, description, labels, unit, opts)
+    }
+
+    fn encode_prometheus(&self) -> String {
+        self.inner.encode_prometheus()
+    }
+
+    fn encode_openmetrics(&self) -> String {
+        self.inner.encode_openmetrics()
     }
 
     fn shutdown(&self) -> Result<()> {
@@ -912,4 +3131,577 @@ mod tests {
         assert!(registry.gauge("test", "test", HashMap::new()).is_err());
         assert!(registry.histogram("test", "test", HashMap::new()).is_err());
     }
+
+    #[test]
+    fn test_capability_registry_forwards_text_exposition_to_inner() {
+        let inner = MemoryMetricsRegistry::new();
+        let checker = Arc::new(AllowAllCapabilityChecker);
+        let registry = CapabilityMetricsRegistry::new(inner, checker);
+
+        registry
+            .counter("requests_total", "Requests", HashMap::new())
+            .unwrap()
+            .increment(1)
+            .unwrap();
+
+        let prometheus = registry.encode_prometheus();
+        assert!(prometheus.contains("requests_total"));
+
+        let openmetrics = registry.encode_openmetrics();
+        assert!(openmetrics.contains("requests_total_total"));
+        assert!(openmetrics.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_quantile_sketch_basic() {
+        let mut sketch = QuantileSketch::new(0.0039);
+        for v in 1..=1000 {
+            sketch.record(v as f64);
+        }
+
+        assert_eq!(sketch.count(), 1000);
+        assert_eq!(sketch.min(), 1.0);
+        assert_eq!(sketch.max(), 1000.0);
+
+        // Bounded relative error: estimate should be within ~1% of the true value.
+        let p50 = sketch.quantile(0.5);
+        assert!((p50 - 500.0).abs() / 500.0 < 0.01, "p50 = {p50}");
+
+        let p99 = sketch.quantile(0.99);
+        assert!((p99 - 990.0).abs() / 990.0 < 0.01, "p99 = {p99}");
+    }
+
+    #[test]
+    fn test_quantile_sketch_merge() {
+        let mut a = QuantileSketch::new(0.0039);
+        let mut b = QuantileSketch::new(0.0039);
+        for v in 1..=500 {
+            a.record(v as f64);
+        }
+        for v in 501..=1000 {
+            b.record(v as f64);
+        }
+
+        a.merge(&b);
+        assert_eq!(a.count(), 1000);
+        assert_eq!(a.min(), 1.0);
+        assert_eq!(a.max(), 1000.0);
+    }
+
+    #[test]
+    fn test_memory_registry_counter_and_gauge() {
+        let registry = MemoryMetricsRegistry::new();
+        let mut labels = HashMap::new();
+        labels.insert("route".to_string(), "/chat".to_string());
+
+        let counter = registry
+            .counter("requests_total", "Total requests", labels.clone())
+            .unwrap();
+        counter.increment(3).unwrap();
+        counter.increment(4).unwrap();
+        assert_eq!(counter.value(), 7);
+
+        let gauge = registry
+            .gauge("queue_depth", "Queue depth", labels)
+            .unwrap();
+        gauge.set(10.0).unwrap();
+        gauge.decrement(2.5).unwrap();
+        assert_eq!(gauge.value(), 7.5);
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.counters.len(), 1);
+        assert_eq!(snapshot.counters[0].value, 7);
+        assert_eq!(snapshot.gauges.len(), 1);
+        assert_eq!(snapshot.gauges[0].value, 7.5);
+    }
+
+    #[test]
+    fn test_memory_registry_histogram_snapshot() {
+        let registry = MemoryMetricsRegistry::new();
+        let histogram = registry
+            .histogram("latency_seconds", "Latency", HashMap::new())
+            .unwrap();
+
+        for v in 1..=100 {
+            histogram.record(v as f64).unwrap();
+        }
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.histograms.len(), 1);
+        let h = &snapshot.histograms[0];
+        assert_eq!(h.count, 100);
+        assert_eq!(h.min, 1.0);
+        assert_eq!(h.max, 100.0);
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains("latency_seconds"));
+    }
+
+    #[test]
+    fn test_memory_registry_idle_eviction() {
+        let policy = EvictionPolicy::new(Duration::from_millis(0), MetricKindMask::COUNTER);
+        let registry = MemoryMetricsRegistry::with_eviction(0.0039, policy);
+
+        let counter = registry
+            .counter("stale_requests", "Stale requests", HashMap::new())
+            .unwrap();
+        counter.increment(1).unwrap();
+
+        // The first sweep only records the current generation (there is no
+        // earlier one to compare against); the sweep inside `snapshot()`
+        // sees it unchanged and, with a zero timeout, evicts the counter.
+        registry.sweep_idle();
+        assert_eq!(registry.snapshot().counters.len(), 0);
+    }
+
+    #[test]
+    fn test_memory_registry_active_metric_not_evicted() {
+        let policy = EvictionPolicy::new(Duration::from_secs(3600), MetricKindMask::ALL);
+        let registry = MemoryMetricsRegistry::with_eviction(0.0039, policy);
+
+        let counter = registry
+            .counter("active_requests", "Active requests", HashMap::new())
+            .unwrap();
+        counter.increment(1).unwrap();
+
+        registry.sweep_idle();
+        assert_eq!(registry.snapshot().counters.len(), 1);
+    }
+
+    #[test]
+    fn test_recency_concurrent_touch_is_never_observed_as_idle() {
+        // A free-running writer thread racing against a fixed wall-clock
+        // timeout is flaky: if the writer is ever descheduled longer than
+        // the timeout, the metric is *legitimately* idle and a non-idle
+        // assertion would fail for a reason that has nothing to do with
+        // `touch()`/`is_idle()`'s ordering guarantee. A `Barrier` bounds
+        // each round instead of relying on scheduling latency: both threads
+        // resync every iteration, so `touch()` and `is_idle()` still race
+        // within a round (exercising the ordering this test cares about),
+        // but no round can silently stretch past the timeout.
+        use std::sync::Barrier;
+
+        let recency = Arc::new(Recency::new());
+        let barrier = Arc::new(Barrier::new(2));
+        let iterations = 2_000;
+
+        let writer = {
+            let recency = Arc::clone(&recency);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                for _ in 0..iterations {
+                    barrier.wait();
+                    recency.touch();
+                }
+            })
+        };
+
+        let timeout = Duration::from_millis(200);
+        for _ in 0..iterations {
+            barrier.wait();
+            assert!(!recency.is_idle(Instant::now(), timeout));
+        }
+
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn test_unit_canonical_label() {
+        assert_eq!(Unit::Bytes.canonical_label(), "bytes");
+        assert_eq!(Unit::Seconds.canonical_label(), "seconds");
+    }
+
+    #[test]
+    fn test_memory_registry_counter_with_unit() {
+        let registry = MemoryMetricsRegistry::new();
+        let counter = registry
+            .counter_with_unit(
+                "bytes_sent",
+                "Bytes sent",
+                HashMap::new(),
+                Some(Unit::Bytes),
+            )
+            .unwrap();
+
+        assert_eq!(counter.unit(), Some(Unit::Bytes));
+        assert_eq!(counter.unit().unwrap().canonical_label(), "bytes");
+    }
+
+    #[test]
+    fn test_build_labels_merges_and_sorts() {
+        let mut labels = HashMap::new();
+        labels.insert("plugin_id".to_string(), "web".to_string());
+        labels.insert("env".to_string(), "prod".to_string());
+
+        let built = build_labels(&labels, &[("request_id", "abc123")]);
+
+        assert_eq!(
+            built,
+            vec![
+                Label::new("env", "prod"),
+                Label::new("plugin_id", "web"),
+                Label::new("request_id", "abc123"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_labels_extra_label_overrides_stored_label_with_same_key() {
+        let mut labels = HashMap::new();
+        labels.insert("plugin_id".to_string(), "web".to_string());
+        labels.insert("env".to_string(), "prod".to_string());
+
+        let built = build_labels(&labels, &[("plugin_id", "override")]);
+
+        // `plugin_id` is present in both the stored labels and
+        // `extra_labels`; the result must contain it exactly once, with
+        // `extra_labels`'s value winning, not a duplicate key.
+        assert_eq!(
+            built,
+            vec![Label::new("env", "prod"), Label::new("plugin_id", "override")]
+        );
+    }
+
+    #[test]
+    fn test_prometheus_counter_increment_with_extra_labels() {
+        let mut labels = HashMap::new();
+        labels.insert("plugin_id".to_string(), "web".to_string());
+        let counter = PrometheusCounter::new("requests_total", "Total requests", labels).unwrap();
+
+        counter.increment_with(1, &[("status", "ok")]).unwrap();
+
+        assert_eq!(counter.value(), 1);
+    }
+
+    #[test]
+    fn test_tcp_broadcaster_drops_oldest_when_no_observer_connected() {
+        let mut broadcaster = TcpBroadcaster::new(2);
+
+        for i in 0..3 {
+            broadcaster.publish(TcpMetricEvent {
+                kind: MetricType::Counter,
+                name: "requests_total".to_string(),
+                labels: vec![],
+                value: i as f64,
+            });
+        }
+
+        assert_eq!(broadcaster.pending.len(), 2);
+        assert_eq!(broadcaster.pending[0].value, 1.0);
+        assert_eq!(broadcaster.pending[1].value, 2.0);
+    }
+
+    #[test]
+    fn test_tcp_registry_streams_events_to_connected_observer() {
+        use std::io::Read;
+
+        let config = MetricsConfig {
+            enabled: true,
+            exporter: MetricsExporter::Tcp,
+            prometheus_enabled: false,
+            prometheus_endpoint: String::new(),
+            tcp_endpoint: "127.0.0.1:0".to_string(),
+            histogram_quantiles: vec![0.5, 0.99],
+            default_labels: HashMap::new(),
+            include_plugin_id: false,
+        };
+        let registry = TcpMetricsRegistry::new(&config).unwrap();
+
+        let mut observer = TcpStream::connect(registry.local_addr()).unwrap();
+        observer
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+
+        let counter = registry
+            .counter("requests_total", "Total requests", HashMap::new())
+            .unwrap();
+        counter.increment(1).unwrap();
+
+        let mut len_buf = [0u8; 4];
+        observer.read_exact(&mut len_buf).unwrap();
+        let len = u32::from_be_bytes(len_buf) as usize;
+        let mut payload = vec![0u8; len];
+        observer.read_exact(&mut payload).unwrap();
+
+        let event: TcpMetricEvent = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(event.name, "requests_total");
+        assert_eq!(event.value, 1.0);
+    }
+
+    #[test]
+    fn test_tcp_registry_shutdown_releases_accept_thread_and_socket() {
+        let config = MetricsConfig {
+            enabled: true,
+            exporter: MetricsExporter::Tcp,
+            prometheus_enabled: false,
+            prometheus_endpoint: String::new(),
+            tcp_endpoint: "127.0.0.1:0".to_string(),
+            histogram_quantiles: vec![0.5, 0.99],
+            default_labels: HashMap::new(),
+            include_plugin_id: false,
+        };
+        let registry = TcpMetricsRegistry::new(&config).unwrap();
+        let addr = registry.local_addr();
+
+        // shutdown() joins the accept thread before returning; if it hung
+        // (e.g. blocked forever in accept()) this test would time out.
+        registry.shutdown().unwrap();
+
+        // The listening socket is released too: a fresh listener can bind
+        // the same address once shutdown completes.
+        assert!(TcpListener::bind(addr).is_ok());
+    }
+
+    #[test]
+    fn test_validate_quantiles_rejects_out_of_range() {
+        assert!(validate_quantiles(&[0.5, 0.99]).is_ok());
+        assert!(validate_quantiles(&[0.5, 1.5]).is_err());
+        assert!(validate_quantiles(&[-0.1]).is_err());
+    }
+
+    #[test]
+    fn test_prometheus_histogram_summary_reflects_recorded_values() {
+        let histogram = PrometheusHistogram::with_quantiles(
+            "request_duration",
+            "Request duration",
+            HashMap::new(),
+            Some(Unit::Seconds),
+            vec![0.5, 0.99],
+        )
+        .unwrap();
+
+        for value in [1.0, 2.0, 3.0, 4.0, 5.0] {
+            histogram.record(value).unwrap();
+        }
+
+        let summary = histogram.summary();
+        assert_eq!(summary.count, 5);
+        assert_eq!(summary.sum, 15.0);
+        assert_eq!(summary.mean, 3.0);
+        assert_eq!(summary.quantiles.len(), 2);
+        assert_eq!(summary.quantiles[0].0, 0.5);
+    }
+
+    #[test]
+    fn test_escape_label_value_escapes_backslash_quote_newline() {
+        assert_eq!(escape_label_value(r#"a\b"#), r#"a\\b"#);
+        assert_eq!(escape_label_value(r#"say "hi""#), r#"say \"hi\""#);
+        assert_eq!(escape_label_value("line1\nline2"), "line1\\nline2");
+    }
+
+    #[test]
+    fn test_encode_prometheus_includes_help_type_and_samples() {
+        let registry = MemoryMetricsRegistry::new();
+        let mut labels = HashMap::new();
+        labels.insert("route".to_string(), "/v1/chat".to_string());
+        registry
+            .counter("requests_total", "Total requests", labels)
+            .unwrap()
+            .increment(3)
+            .unwrap();
+        registry
+            .gauge("queue_depth", "Queue depth", HashMap::new())
+            .unwrap()
+            .set(7.0)
+            .unwrap();
+
+        let text = registry.encode_prometheus();
+        assert!(text.contains("# HELP requests_total Total requests\n"));
+        assert!(text.contains("# TYPE requests_total counter\n"));
+        assert!(text.contains(r#"requests_total{route="/v1/chat"} 3"#));
+        assert!(text.contains("# TYPE queue_depth gauge\n"));
+        assert!(text.contains("queue_depth 7"));
+    }
+
+    #[test]
+    fn test_encode_prometheus_renders_histogram_buckets_sum_and_count() {
+        let registry = MemoryMetricsRegistry::new();
+        let histogram = registry
+            .histogram("latency_seconds", "Latency", HashMap::new())
+            .unwrap();
+        for v in 1..=10 {
+            histogram.record(v as f64).unwrap();
+        }
+
+        let text = registry.encode_prometheus();
+        assert!(text.contains("# TYPE latency_seconds histogram\n"));
+        assert!(text.contains("latency_seconds_bucket{le=\"+Inf\"} 10"));
+        assert!(text.contains("latency_seconds_sum "));
+        assert!(text.contains("latency_seconds_count 10"));
+    }
+
+    #[test]
+    fn test_encode_openmetrics_appends_total_suffix_and_eof() {
+        let registry = MemoryMetricsRegistry::new();
+        registry
+            .counter("requests_total", "Total requests", HashMap::new())
+            .unwrap()
+            .increment(1)
+            .unwrap();
+
+        let text = registry.encode_openmetrics();
+        assert!(text.contains("requests_total_total 1"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_encode_prometheus_empty_registry_produces_empty_string() {
+        let registry = MemoryMetricsRegistry::new();
+        assert_eq!(registry.encode_prometheus(), "");
+    }
+
+    #[test]
+    fn test_time_histogram_records_elapsed_on_drop() {
+        let registry = MemoryMetricsRegistry::new();
+        {
+            let _timer = registry
+                .time_histogram("op_duration_seconds", "Operation duration", HashMap::new())
+                .unwrap();
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.histograms.len(), 1);
+        assert_eq!(snapshot.histograms[0].count, 1);
+        assert!(snapshot.histograms[0].min > 0.0);
+    }
+
+    #[test]
+    fn test_time_macro_records_duration_and_returns_value() {
+        let registry = MemoryMetricsRegistry::new();
+        let histogram = registry
+            .histogram("block_duration_seconds", "Block duration", HashMap::new())
+            .unwrap();
+
+        let result = crate::time!(histogram, {
+            thread::sleep(Duration::from_millis(5));
+            42
+        });
+
+        assert_eq!(result, 42);
+        let summary = histogram.summary();
+        assert_eq!(summary.count, 1);
+    }
+
+    #[test]
+    fn test_histogram_opts_linear_and_exponential() {
+        let linear = HistogramOpts::linear(1.0, 2.0, 4).unwrap();
+        assert_eq!(linear.buckets(), &[1.0, 3.0, 5.0, 7.0]);
+
+        let exponential = HistogramOpts::exponential(1.0, 2.0, 4).unwrap();
+        assert_eq!(exponential.buckets(), &[1.0, 2.0, 4.0, 8.0]);
+    }
+
+    #[test]
+    fn test_histogram_opts_rejects_non_increasing_or_non_finite_bounds() {
+        assert!(HistogramOpts::explicit(vec![1.0, 1.0]).is_err());
+        assert!(HistogramOpts::explicit(vec![2.0, 1.0]).is_err());
+        assert!(HistogramOpts::explicit(vec![1.0, f64::NAN]).is_err());
+        assert!(HistogramOpts::explicit(vec![1.0, f64::INFINITY]).is_err());
+        assert!(HistogramOpts::explicit(vec![1.0, 2.0, 3.0]).is_ok());
+    }
+
+    #[test]
+    fn test_memory_registry_histogram_with_opts_tracks_cumulative_buckets() {
+        let registry = MemoryMetricsRegistry::new();
+        let opts = HistogramOpts::explicit(vec![1.0, 5.0, 10.0]).unwrap();
+        let histogram = registry
+            .histogram_with_opts("latency_seconds", "Latency", HashMap::new(), None, opts)
+            .unwrap();
+
+        for v in [0.5, 2.0, 7.0, 20.0] {
+            histogram.record(v).unwrap();
+        }
+
+        let snapshot = registry.snapshot();
+        let h = &snapshot.histograms[0];
+        assert_eq!(h.count, 4);
+        assert_eq!(h.buckets, vec![(1.0, 1), (5.0, 2), (10.0, 3)]);
+    }
+
+    #[test]
+    fn test_encode_prometheus_uses_real_buckets_when_configured() {
+        let registry = MemoryMetricsRegistry::new();
+        let opts = HistogramOpts::explicit(vec![1.0, 5.0]).unwrap();
+        let histogram = registry
+            .histogram_with_opts("latency_seconds", "Latency", HashMap::new(), None, opts)
+            .unwrap();
+        histogram.record(0.5).unwrap();
+        histogram.record(3.0).unwrap();
+
+        let text = registry.encode_prometheus();
+        assert!(text.contains("latency_seconds_bucket{le=\"1\"} 1"));
+        assert!(text.contains("latency_seconds_bucket{le=\"5\"} 2"));
+        assert!(text.contains("latency_seconds_bucket{le=\"+Inf\"} 2"));
+    }
+
+    #[test]
+    fn test_memory_registry_counter_fast_path_reuses_same_state() {
+        let registry = MemoryMetricsRegistry::new();
+        let first = registry
+            .counter("hits_total", "Hits", HashMap::new())
+            .unwrap();
+        first.increment(5).unwrap();
+
+        // A second lookup for the same name+labels should hit the
+        // read-lock fast path and observe the same underlying state.
+        let second = registry
+            .counter("hits_total", "Hits", HashMap::new())
+            .unwrap();
+        assert_eq!(second.value(), 5);
+
+        second.increment(2).unwrap();
+        assert_eq!(first.value(), 7);
+    }
+
+    #[test]
+    fn test_labeled_metric_handle_caches_and_increments_counter() {
+        let registry = MemoryMetricsRegistry::new();
+        let handle = registry
+            .counter_handle("requests_total", "Requests", HashMap::new())
+            .unwrap();
+
+        handle.observe(1.0).unwrap();
+        handle.observe(1.0).unwrap();
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.counters[0].value, 2);
+    }
+
+    #[test]
+    fn test_labeled_metric_handle_gauge_and_histogram() {
+        let registry = MemoryMetricsRegistry::new();
+
+        let gauge_handle = registry
+            .gauge_handle("queue_depth", "Queue depth", HashMap::new())
+            .unwrap();
+        gauge_handle.observe(3.0).unwrap();
+        assert_eq!(registry.snapshot().gauges[0].value, 3.0);
+
+        let histogram_handle = registry
+            .histogram_handle("latency_seconds", "Latency", HashMap::new())
+            .unwrap();
+        histogram_handle.observe(1.5).unwrap();
+        assert_eq!(registry.snapshot().histograms[0].count, 1);
+    }
+
+    #[test]
+    fn test_memory_histogram_summary_matches_snapshot_quantiles() {
+        let registry = MemoryMetricsRegistry::new();
+        let histogram = registry
+            .histogram("latency_seconds", "Latency", HashMap::new())
+            .unwrap();
+
+        for v in [0.1, 0.5, 1.0, 5.0, 10.0] {
+            histogram.record(v).unwrap();
+        }
+
+        let summary = histogram.summary();
+        let snapshot = registry.snapshot();
+        let h = &snapshot.histograms[0];
+
+        assert_eq!(summary.quantiles.len(), 4);
+        assert_eq!(summary.quantiles[3].0, 0.999);
+        assert_eq!(summary.quantiles[3].1, h.p999);
+    }
 }